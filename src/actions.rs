@@ -1,7 +1,11 @@
 #[derive(Debug, Clone)]
 pub enum ActionState {
+    /// Waiting for its turn.
+    Queued,
     Pending,
     Done,
+    /// Stopped halfway, what is left is incomplete.
+    PartiallyDeleted,
     Failed(String),
 }
 
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Files whose mtime tells when git last touched the project.
+const GIT_FILES: [&str; 2] = [".git/HEAD", ".git/index"];
+
+/// Files marking a directory as a project of its own.
+const PROJECT_MARKERS: [&str; 2] = ["package.json", ".git"];
+
+/// How deep below the project root sources are looked at.
+const MAX_DEPTH: usize = 16;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(path).ok()?.modified().ok()
+}
+
+fn is_project(dir: &Path) -> bool {
+    PROJECT_MARKERS
+        .iter()
+        .any(|marker| fs::symlink_metadata(dir.join(marker)).is_ok())
+}
+
+/// When the project at `project_root` was last worked on.
+///
+/// This is the newest mtime among the project's files, including its manifest and
+/// lockfile, and git's `HEAD` and `index`. The candidate's `paths`, any `node_modules`
+/// and the rest of `.git` are left out, since installs and fetches touch them without
+/// anyone working on the project. Nested projects have an activity of their own and are
+/// left out too, and the walk stops [`MAX_DEPTH`] levels down.
+///
+/// Entries that can't be read are passed to `report` with the reason, and left out.
+pub fn last_active(
+    project_root: &Path,
+    paths: &[PathBuf],
+    report: &mut dyn FnMut(PathBuf, String),
+) -> Option<SystemTime> {
+    let mut newest_file = None;
+    let walker = WalkDir::new(project_root)
+        .follow_links(false)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_dir();
+            !paths.iter().any(|path| path == entry.path())
+                && !(is_dir && (entry.file_name() == "node_modules" || entry.file_name() == ".git"))
+                && !(is_dir && entry.depth() > 0 && is_project(entry.path()))
+        });
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().unwrap_or(project_root).to_path_buf();
+                let message = e
+                    .io_error()
+                    .map_or_else(|| e.to_string(), |io_error| io_error.to_string());
+                report(path, message);
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        match entry.metadata() {
+            // Platforms without mtimes leave the project's activity unknown.
+            Ok(metadata) => newest_file = newest_file.max(metadata.modified().ok()),
+            Err(e) => report(entry.path().to_path_buf(), e.to_string()),
+        }
+    }
+    // Projects without git simply don't have these.
+    let newest_git = GIT_FILES
+        .iter()
+        .filter_map(|file| modified(&project_root.join(file)))
+        .max();
+    newest_file.max(newest_git)
+}
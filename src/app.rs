@@ -1,9 +1,21 @@
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 use crate::actions::ActionState;
+use crate::config::ConfirmThreshold;
+use crate::deleter::{DeleteQueue, Removed};
 use crate::dir_entry_item::DirEntryItem;
+use crate::dir_size::{Inode, LinkedFile};
+use crate::effects::{DeleteOptions, ScanOptions};
 use crate::event::{DirDelete, DirSearch};
+use crate::failure::DeleteFailure;
 use crate::list::{Filterable, StatefulList, Toggle};
+use crate::scan_control::ScanControl;
+use crate::workspace::Workspace;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 impl PartialEq for DirEntryItem {
     fn eq(&self, other: &Self) -> bool {
@@ -17,6 +29,74 @@ pub enum GroupSelection {
     None,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Largest first.
+    Size,
+    /// Least recently active first.
+    Age,
+    Path,
+}
+
+impl SortOrder {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Size => "size",
+            SortOrder::Age => "age",
+            SortOrder::Path => "path",
+        }
+    }
+
+    fn next(&self) -> SortOrder {
+        match self {
+            SortOrder::Size => SortOrder::Age,
+            SortOrder::Age => SortOrder::Path,
+            SortOrder::Path => SortOrder::Size,
+        }
+    }
+}
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Hides projects that were active more recently than the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeFilter {
+    OneMonth,
+    ThreeMonths,
+    SixMonths,
+    OneYear,
+}
+
+impl AgeFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AgeFilter::OneMonth => "1 month",
+            AgeFilter::ThreeMonths => "3 months",
+            AgeFilter::SixMonths => "6 months",
+            AgeFilter::OneYear => "1 year",
+        }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        Duration::from_secs(match self {
+            AgeFilter::OneMonth => 30 * DAY,
+            AgeFilter::ThreeMonths => 91 * DAY,
+            AgeFilter::SixMonths => 182 * DAY,
+            AgeFilter::OneYear => 365 * DAY,
+        })
+    }
+
+    fn next(current: Option<AgeFilter>) -> Option<AgeFilter> {
+        match current {
+            None => Some(AgeFilter::OneMonth),
+            Some(AgeFilter::OneMonth) => Some(AgeFilter::ThreeMonths),
+            Some(AgeFilter::ThreeMonths) => Some(AgeFilter::SixMonths),
+            Some(AgeFilter::SixMonths) => Some(AgeFilter::OneYear),
+            Some(AgeFilter::OneYear) => None,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Log {
     pub current: ItemCounter,
@@ -44,6 +124,15 @@ impl Log {
             false
         }
     }
+    fn cancelled(&mut self, size: u64) -> bool {
+        self.current.remove(size)
+    }
+    fn restored(&mut self, size: u64) -> bool {
+        self.history.remove(size)
+    }
+    fn retried(&mut self, size: u64) -> bool {
+        self.failed.remove(size)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -67,7 +156,7 @@ impl ItemCounter {
 
     // Remove an item with a given size, if possible without underflowing
     fn remove(&mut self, size: u64) -> bool {
-        if self.count > 1 && self.total_size > size {
+        if self.count > 0 && self.total_size >= size {
             self.count -= 1;
             self.total_size -= size;
             true
@@ -77,6 +166,170 @@ impl ItemCounter {
     }
 }
 
+/// The hard-linked files of the selected items, tallied across them.
+///
+/// With pnpm every file is linked to the store, so the tally only changes for the items
+/// that joined or left the selection, or got sized again, rather than being redone on
+/// every event.
+#[derive(Debug, Default)]
+pub struct LinkTally {
+    /// The shared files counted for each item.
+    items: HashMap<PathBuf, Arc<[(Inode, LinkedFile)]>>,
+    files: HashMap<Inode, LinkedFile>,
+    /// Allocated bytes of the files whose every link is counted.
+    freed: u64,
+}
+
+impl LinkTally {
+    fn add(&mut self, files: &[(Inode, LinkedFile)]) {
+        for (inode, file) in files {
+            let known = self
+                .files
+                .entry(*inode)
+                .or_insert(LinkedFile { links: 0, ..*file });
+            let was_complete = known.is_complete();
+            known.links = known.links.saturating_add(file.links);
+            if !was_complete && known.is_complete() {
+                self.freed = self.freed.saturating_add(known.allocated);
+            }
+        }
+    }
+
+    fn remove(&mut self, files: &[(Inode, LinkedFile)]) {
+        for (inode, file) in files {
+            let Some(known) = self.files.get_mut(inode) else {
+                continue;
+            };
+            let was_complete = known.is_complete();
+            known.links = known.links.saturating_sub(file.links);
+            if was_complete && !known.is_complete() {
+                self.freed = self.freed.saturating_sub(known.allocated);
+            }
+            if known.links == 0 {
+                self.files.remove(inode);
+            }
+        }
+    }
+
+    /// Brings the tally to the `selected` items, and returns the bytes freed by the
+    /// files they hold every link to.
+    fn update<'a>(&mut self, selected: impl Iterator<Item = &'a DirEntryItem>) -> u64 {
+        let mut kept: HashSet<&Path> = HashSet::new();
+        for item in selected {
+            let path = item.entry.path();
+            kept.insert(path);
+            let counted = self
+                .items
+                .get(path)
+                .is_some_and(|files| Arc::ptr_eq(files, &item.shared_files));
+            if !counted {
+                if let Some(old) = self
+                    .items
+                    .insert(path.to_path_buf(), item.shared_files.clone())
+                {
+                    self.remove(&old);
+                }
+                self.add(&item.shared_files);
+            }
+        }
+        let gone: Vec<PathBuf> = self
+            .items
+            .keys()
+            .filter(|path| !kept.contains(path.as_path()))
+            .cloned()
+            .collect();
+        for path in gone {
+            if let Some(old) = self.items.remove(&path) {
+                self.remove(&old);
+            }
+        }
+        self.freed
+    }
+}
+
+/// What has to be typed to confirm deleting a selection past the threshold.
+pub const CONFIRM_WORD: &str = "delete";
+
+/// A deletion waiting to be confirmed.
+#[derive(Debug)]
+pub struct Confirmation {
+    pub items: Vec<DirEntryItem>,
+    /// Space deleting the items frees.
+    pub size: u64,
+    /// The selection is past the threshold, [`CONFIRM_WORD`] has to be typed.
+    pub needs_typing: bool,
+    /// What was typed so far.
+    pub typed: String,
+}
+
+impl Confirmation {
+    /// Directories deleting the items removes, counting each member of a workspace.
+    pub fn directories(&self) -> usize {
+        self.items.iter().map(|item| item.candidates().len()).sum()
+    }
+}
+
+/// Progress of the deletions going on, since the first of them started.
+#[derive(Debug)]
+pub struct DeletionProgress {
+    pub started: Instant,
+    /// Bytes freed once every deletion is done.
+    pub total: u64,
+    pub freed: u64,
+    /// Time spent paused, not counting the current pause.
+    paused_for: Duration,
+    paused_at: Option<Instant>,
+}
+
+impl DeletionProgress {
+    fn new() -> Self {
+        DeletionProgress {
+            started: Instant::now(),
+            total: 0,
+            freed: 0,
+            paused_for: Duration::ZERO,
+            paused_at: None,
+        }
+    }
+
+    fn pause(&mut self) {
+        self.paused_at.get_or_insert_with(Instant::now);
+    }
+
+    fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_for += paused_at.elapsed();
+        }
+    }
+
+    /// Time spent deleting, pauses left out.
+    fn elapsed(&self) -> Duration {
+        let paused = self.paused_for + self.paused_at.map_or(Duration::ZERO, |at| at.elapsed());
+        self.started.elapsed().saturating_sub(paused)
+    }
+
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.freed as f64 / self.total as f64).min(1.0)
+        }
+    }
+
+    /// Bytes freed per second.
+    pub fn throughput(&self) -> f64 {
+        self.freed as f64 / self.elapsed().as_secs_f64().max(0.001)
+    }
+
+    /// Time left at the current throughput, unknown until something was freed.
+    pub fn eta(&self) -> Option<Duration> {
+        let throughput = self.throughput();
+        (throughput > 0.0).then(|| {
+            Duration::from_secs_f64(self.total.saturating_sub(self.freed) as f64 / throughput)
+        })
+    }
+}
+
 /// Application.
 #[derive(Debug)]
 pub struct App {
@@ -87,8 +340,61 @@ pub struct App {
     pub filter_input: Option<String>,
     pub deleting_size: Log,
     pub selected: ItemCounter,
+    /// Hard links held by the selection.
+    pub selected_links: LinkTally,
     pub search_counter: u64,
     pub search_results: u64,
+    /// Mount points the scan didn't cross into.
+    pub skipped_mounts: u64,
+    /// Paths the scan couldn't read, and why.
+    pub scan_errors: Vec<(PathBuf, String)>,
+    /// Show the scan errors panel.
+    pub show_errors: bool,
+    /// Ignore files the scan went by, the index is only valid while they don't change.
+    pub ignore_files: Vec<PathBuf>,
+    /// First line shown in the scan errors panel.
+    pub error_scroll: usize,
+    /// Show the project name instead of the path when it is known.
+    pub show_project_names: bool,
+    /// Sort applied to the list, items stay in discovery order until one is picked.
+    pub sort: Option<SortOrder>,
+    /// Only show projects inactive for at least this long.
+    pub min_age: Option<AgeFilter>,
+    /// Pauses and cancels the current scan.
+    pub scan_control: Arc<ScanControl>,
+    /// What the current scan looks for, to start it again.
+    pub scan_options: Option<ScanOptions>,
+    /// Set while a cancelled scan's events may still be queued, until the new one starts.
+    pub awaiting_scan_start: bool,
+    /// Paths selected before a rescan, selected again when found.
+    pub kept_selection: HashSet<PathBuf>,
+    /// Whether to go through deletions without removing anything, or move to the trash.
+    pub delete_options: DeleteOptions,
+    /// Paths going through a dry run.
+    pub dry_running: HashSet<PathBuf>,
+    /// The directories each item of the last dry run would have removed, and the space
+    /// it would have freed.
+    pub dry_run_report: Vec<(Vec<PathBuf>, u64)>,
+    /// Show the dry run report panel.
+    pub show_report: bool,
+    /// First line shown in the dry run report panel.
+    pub report_scroll: usize,
+    /// The deletion shown for confirmation.
+    pub confirmation: Option<Confirmation>,
+    /// Selections past which confirming needs typing.
+    pub confirm_threshold: ConfirmThreshold,
+    /// Overall progress, while deleting.
+    pub deletion: Option<DeletionProgress>,
+    /// Deletes the items, a bounded number at a time.
+    pub delete_queue: Arc<DeleteQueue>,
+    /// Why the last restore from quarantine failed.
+    pub restore_error: Option<String>,
+    /// Items whose deletion failed, and why.
+    pub delete_failures: Vec<(PathBuf, DeleteFailure)>,
+    /// Show the failed deletions panel.
+    pub show_failures: bool,
+    /// First line shown in the failed deletions panel.
+    pub failure_scroll: usize,
 }
 
 impl Default for App {
@@ -100,8 +406,34 @@ impl Default for App {
             filter_input: None,
             deleting_size: Log::default(),
             selected: ItemCounter::default(),
+            selected_links: LinkTally::default(),
             search_counter: 0,
             search_results: 0,
+            skipped_mounts: 0,
+            scan_errors: Vec::new(),
+            show_errors: false,
+            ignore_files: Vec::new(),
+            error_scroll: 0,
+            show_project_names: false,
+            sort: None,
+            min_age: None,
+            scan_control: Arc::default(),
+            scan_options: None,
+            awaiting_scan_start: false,
+            kept_selection: HashSet::new(),
+            delete_options: DeleteOptions::default(),
+            dry_running: HashSet::new(),
+            dry_run_report: Vec::new(),
+            show_report: false,
+            report_scroll: 0,
+            confirmation: None,
+            confirm_threshold: ConfirmThreshold::default(),
+            deletion: None,
+            delete_queue: Arc::default(),
+            restore_error: None,
+            delete_failures: Vec::new(),
+            show_failures: false,
+            failure_scroll: 0,
         }
     }
 }
@@ -111,23 +443,315 @@ impl App {
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
+        self.scan_control.cancel();
         self.running = false;
     }
 
+    pub fn toggle_errors(&mut self) {
+        self.show_errors = !self.show_errors;
+    }
+
+    /// Scrolls the scan errors panel by `lines`, up when negative.
+    pub fn scroll_errors(&mut self, lines: isize) {
+        let last = self.scan_errors.len().saturating_sub(1);
+        self.error_scroll = self.error_scroll.saturating_add_signed(lines).min(last);
+    }
+
+    pub fn toggle_failures(&mut self) {
+        self.show_failures = !self.show_failures;
+    }
+
+    /// Scrolls the failed deletions panel by `lines`, up when negative.
+    pub fn scroll_failures(&mut self, lines: isize) {
+        let last = self.failure_lines().saturating_sub(1);
+        self.failure_scroll = self.failure_scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Lines the failed deletions panel takes.
+    pub fn failure_lines(&self) -> usize {
+        self.delete_failures
+            .iter()
+            .map(|(_, failure)| 2 + usize::from(failure.suggestion.is_some()))
+            .sum()
+    }
+
+    pub fn toggle_dry_run(&mut self) {
+        self.delete_options.dry_run = !self.delete_options.dry_run;
+    }
+
+    pub fn toggle_report(&mut self) {
+        self.show_report = !self.show_report;
+    }
+
+    /// How many directories the last dry run would have removed, one line each.
+    pub fn dry_run_directories(&self) -> usize {
+        self.dry_run_report
+            .iter()
+            .map(|(paths, _)| paths.len())
+            .sum()
+    }
+
+    /// Scrolls the dry run report panel by `lines`, up when negative.
+    pub fn scroll_report(&mut self, lines: isize) {
+        let last = self.dry_run_directories().saturating_sub(1);
+        self.report_scroll = self.report_scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Asks to confirm deleting the selection, if there is one.
+    pub fn request_delete(&mut self) {
+        let items: Vec<DirEntryItem> = self.list.items_to_delete().cloned().collect();
+        if items.is_empty() {
+            return;
+        }
+        let size = self.selected.total_size;
+        let mut confirmation = Confirmation {
+            items,
+            size,
+            needs_typing: false,
+            typed: String::new(),
+        };
+        confirmation.needs_typing = self
+            .confirm_threshold
+            .is_exceeded(confirmation.directories(), size);
+        self.confirmation = Some(confirmation);
+    }
+
+    /// Returns the items to delete once confirmed, `None` while the word isn't typed in
+    /// full.
+    pub fn confirm_delete(&mut self) -> Option<Vec<DirEntryItem>> {
+        let confirmation = self.confirmation.take()?;
+        if confirmation.needs_typing && confirmation.typed != CONFIRM_WORD {
+            self.confirmation = Some(confirmation);
+            return None;
+        }
+        self.start_delete(&confirmation.items);
+        // What was selected at once is on its way, the next selection starts over.
+        self.list.group_selection = None;
+        self.refresh_selected();
+        Some(confirmation.items)
+    }
+
+    pub fn cancel_delete(&mut self) {
+        self.confirmation = None;
+    }
+
+    pub fn type_confirmation(&mut self, c: char) {
+        if let Some(confirmation) = self.confirmation.as_mut() {
+            confirmation.typed.push(c);
+        }
+    }
+
+    pub fn erase_confirmation(&mut self) {
+        if let Some(confirmation) = self.confirmation.as_mut() {
+            confirmation.typed.pop();
+        }
+    }
+
+    /// Marks `items` as queued for deletion, and accounts for them in the progress.
+    pub fn start_delete(&mut self, items: &[DirEntryItem]) {
+        // Deletions queued while others go on add to their progress.
+        if self.deleting_size.current.count == 0 {
+            self.deletion = Some(DeletionProgress::new());
+        }
+        let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
+        let paths: HashSet<&Path> = items.iter().map(|item| item.entry.path()).collect();
+        self.list.mutate_each(
+            |item| paths.contains(item.entry.path()),
+            |item| {
+                self.deleting_size.add(item.exclusive_size);
+                deletion.total = deletion.total.saturating_add(item.exclusive_size);
+                item.removed = Removed::default();
+                item.delete_state = Some(ActionState::Queued);
+            },
+        );
+        if !self.delete_options.dry_run {
+            return;
+        }
+        // A new dry run, rather than more items for the one going on.
+        if self.dry_running.is_empty() {
+            self.dry_run_report.clear();
+            self.report_scroll = 0;
+            self.show_report = false;
+        }
+        self.dry_running
+            .extend(items.iter().map(|item| item.entry.path().to_path_buf()));
+    }
+
+    pub fn toggle_delete_pause(&mut self) {
+        if self.delete_queue.is_paused() {
+            self.delete_queue.resume();
+            if let Some(deletion) = self.deletion.as_mut() {
+                deletion.resume();
+            }
+        } else {
+            self.delete_queue.pause();
+            if let Some(deletion) = self.deletion.as_mut() {
+                deletion.pause();
+            }
+        }
+    }
+
+    /// Drops the queued deletions and stops the ones going on, which report back as
+    /// cancelled.
+    pub fn cancel_deletions(&mut self) {
+        let dropped: HashSet<PathBuf> = self.delete_queue.cancel().into_iter().collect();
+        if let Some(deletion) = self.deletion.as_mut() {
+            deletion.resume();
+        }
+        for path in &dropped {
+            self.dry_running.remove(path);
+        }
+        let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
+        self.list.mutate_each(
+            |item| {
+                dropped.contains(item.entry.path())
+                    && matches!(item.delete_state, Some(ActionState::Queued))
+            },
+            |item| {
+                self.deleting_size.cancelled(item.exclusive_size);
+                deletion.total = deletion.total.saturating_sub(item.exclusive_size);
+                item.delete_state = None;
+            },
+        );
+        self.refresh_selected();
+    }
+
+    /// Renames the selected item back from quarantine, if it's still there.
+    pub fn restore_selected(&mut self) {
+        let quarantine = self.delete_queue.quarantine();
+        let mut result = None;
+        self.list.mutate_selected(|item| {
+            if !matches!(item.delete_state, Some(ActionState::Done))
+                || !quarantine.is_quarantined(item.entry.path())
+            {
+                return false;
+            }
+            let restored = quarantine.restore(item.entry.path());
+            if restored.is_ok() {
+                self.deleting_size.restored(item.exclusive_size);
+                item.removed = Removed::default();
+                item.delete_state = None;
+            }
+            result = Some(restored.map_err(|e| format!("{}: {}", item.entry.path().display(), e)));
+            true
+        });
+        match result {
+            Some(Ok(())) => self.restore_error = None,
+            Some(Err(e)) => self.restore_error = Some(e),
+            None => {}
+        }
+        self.refresh_selected();
+    }
+
+    /// Queues the items whose deletion failed again, and returns them to delete.
+    pub fn retry_failed(&mut self) -> Vec<DirEntryItem> {
+        let items: Vec<DirEntryItem> = self
+            .list
+            .items()
+            .filter(|item| matches!(item.delete_state, Some(ActionState::Failed(_))))
+            .cloned()
+            .collect();
+        for item in &items {
+            self.deleting_size.retried(item.exclusive_size);
+        }
+        // Failures of items no longer listed as failed stay, they aren't retried.
+        self.delete_failures
+            .retain(|(path, _)| !items.iter().any(|item| item.entry.path() == path));
+        self.failure_scroll = 0;
+        self.start_delete(&items);
+        items
+    }
+
+    /// Deletes `delta` more items at the same time, fewer when negative.
+    pub fn change_delete_jobs(&mut self, delta: isize) {
+        let limit = self.delete_queue.jobs_limit().saturating_add_signed(delta);
+        self.delete_queue.set_jobs_limit(limit);
+    }
+
+    pub fn toggle_scan_pause(&mut self) {
+        if self.scan_control.is_paused() {
+            self.scan_control.resume();
+        } else {
+            self.scan_control.pause();
+        }
+    }
+
+    /// Stops the scan, keeping what was found so far. Sizes still pending stay unknown.
+    pub fn cancel_scan(&mut self) {
+        self.scan_control.cancel();
+        if self.list.is_scanning() {
+            self.list
+                .set_scanning(Some(ActionState::Failed("cancelled".to_string())));
+        }
+        self.list.mutate_each(
+            |item| item.is_sizing(),
+            |item| item.size_state = ActionState::Failed("scan cancelled".to_string()),
+        );
+        self.list.mutate_each(
+            |item| matches!(item.breakdown_state, Some(ActionState::Pending)),
+            |item| item.breakdown_state = Some(ActionState::Failed("scan cancelled".to_string())),
+        );
+        self.refresh_view();
+    }
+
+    /// Cancels the scan and clears the list for a new one, which is up to the caller
+    /// to start with the returned options and control.
+    ///
+    /// Selected items are selected again when the new scan finds them. Nothing happens
+    /// while deletions are running, since they report back to the current list.
+    pub fn rescan(&mut self) -> Option<(ScanOptions, Arc<ScanControl>)> {
+        if self.deleting_size.current.count > 0 {
+            return None;
+        }
+        let mut options = self.scan_options.clone()?;
+        // A rescan walks the roots, whatever the index says.
+        options.cached.clear();
+        self.scan_control.cancel();
+        self.scan_control = Arc::default();
+        self.awaiting_scan_start = true;
+        self.kept_selection = self
+            .list
+            .items()
+            .filter(|item| item.is_on() && item.delete_state.is_none())
+            .map(|item| item.entry.path().to_path_buf())
+            .collect();
+        let group_selection = self.list.group_selection.take();
+        self.list = StatefulList::default();
+        self.list.group_selection = group_selection;
+        self.search_counter = 0;
+        self.search_results = 0;
+        self.skipped_mounts = 0;
+        self.scan_errors.clear();
+        self.error_scroll = 0;
+        self.ignore_files.clear();
+        self.refresh_view();
+        Some((options, self.scan_control.clone()))
+    }
+
     pub fn push(&mut self, item: DirEntryItem) {
         self.list.push(item);
     }
 
+    /// Recomputes the selection totals from the items that would be deleted.
+    ///
+    /// Sizes arrive after the items themselves, so the totals are derived from the
+    /// list rather than maintained incrementally. A hard-linked file only counts once
+    /// every link to it is in the selection, since that's when deleting frees it; those
+    /// are tallied per item in [`LinkTally`].
+    fn refresh_selected(&mut self) {
+        let mut selected = ItemCounter::new();
+        for item in self.list.items_to_delete() {
+            selected.add(item.exclusive_size);
+        }
+        let freed_by_links = self.selected_links.update(self.list.items_to_delete());
+        selected.total_size = selected.total_size.saturating_add(freed_by_links);
+        self.selected = selected;
+    }
+
     pub fn toggle_selected_item(&mut self) {
         if self.list.mutate_selected(|item| {
             if item.can_toggle() {
-                if item.is_on() {
-                    item.set_is_on(false);
-                    self.selected.add(item.size);
-                } else {
-                    self.selected.remove(item.size);
-                    item.set_is_on(true);
-                }
+                item.toggle();
                 true
             } else {
                 false
@@ -135,6 +759,103 @@ impl App {
         }) {
             self.list.group_selection = None;
         }
+        self.refresh_selected();
+    }
+
+    /// Adds `item` to the group standing for `workspace`, creating it on the first member.
+    fn add_to_workspace(&mut self, item: DirEntryItem, workspace: Arc<Workspace>) {
+        let mut item = Some(item);
+        self.list.mutate_where(
+            |group| {
+                group.is_group()
+                    && group.entry.path() == workspace.root
+                    && group.delete_state.is_none()
+            },
+            |group| group.add_member(item.take().expect("The member is only added once.")),
+        );
+        let Some(item) = item else {
+            return;
+        };
+        // A workspace root that just disappeared leaves the member on its own.
+        let Some(mut group) = DirEntryItem::group(item.clone(), workspace) else {
+            self.list.push(item);
+            return;
+        };
+        if self.kept_selection.remove(group.entry.path()) {
+            group.set_is_on(true);
+        }
+        self.list.push(group);
+    }
+
+    /// Expands or collapses the selected item.
+    ///
+    /// Returns the item when its breakdown is still to be computed. A group shows its
+    /// members instead.
+    pub fn toggle_expanded(&mut self) -> Option<DirEntryItem> {
+        let cancelled = self.scan_control.is_cancelled();
+        let mut to_break_down = None;
+        self.list.mutate_selected(|item| {
+            item.expanded = !item.expanded;
+            if item.expanded && item.breakdown_state.is_none() && !item.is_group() {
+                // Breakdowns are part of the scan, there's none after cancelling it.
+                if cancelled {
+                    item.breakdown_state = Some(ActionState::Failed("scan cancelled".to_string()));
+                } else {
+                    item.breakdown_state = Some(ActionState::Pending);
+                    to_break_down = Some(item.clone());
+                }
+            }
+            true
+        });
+        to_break_down
+    }
+
+    /// Re-applies the sort order and the filters to the list.
+    ///
+    /// Called whenever items arrive or change, or the sort or a filter changes.
+    fn refresh_view(&mut self) {
+        if let Some(sort) = self.sort {
+            self.list.sort_by(|a, b| match sort {
+                SortOrder::Size => b.size.cmp(&a.size),
+                // Unknown ages sort last.
+                SortOrder::Age => match (a.last_active, b.last_active) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+                SortOrder::Path => a.entry.path().cmp(b.entry.path()),
+            });
+        }
+        let filter_input = self.filter_input.clone();
+        let min_age = self.min_age.map(|filter| filter.threshold());
+        if filter_input.is_none() && min_age.is_none() {
+            self.list.clear_filter();
+        } else {
+            self.list.apply_filter(|item| {
+                let matches_input = filter_input
+                    .as_ref()
+                    .is_none_or(|input| item.entry.path().to_string_lossy().contains(input));
+                let old_enough =
+                    min_age.is_none_or(|min_age| item.age().is_some_and(|age| age >= min_age));
+                matches_input && old_enough
+            });
+        }
+        self.refresh_selected();
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = Some(self.sort.map_or(SortOrder::Size, |sort| sort.next()));
+        self.refresh_view();
+    }
+
+    pub fn cycle_age_filter(&mut self) {
+        self.min_age = AgeFilter::next(self.min_age);
+        self.refresh_view();
+    }
+
+    pub fn toggle_project_names(&mut self) {
+        self.show_project_names = !self.show_project_names;
     }
 
     pub fn start_search_entry(&mut self) {
@@ -151,6 +872,7 @@ impl App {
             true
         });
         self.list.group_selection = None;
+        self.refresh_selected();
         self.list.next()
     }
 
@@ -160,6 +882,7 @@ impl App {
             true
         });
         self.list.group_selection = None;
+        self.refresh_selected();
         self.list.next()
     }
 
@@ -189,9 +912,16 @@ impl App {
             .or(Some(GroupSelection::All));
 
         self.list.group_selection = group_selection;
+        self.refresh_selected();
     }
 
     pub fn handle_search(&mut self, d: DirSearch) {
+        if self.awaiting_scan_start {
+            if !matches!(d, DirSearch::Started) {
+                return;
+            }
+            self.awaiting_scan_start = false;
+        }
         match d {
             DirSearch::Started => self.list.set_scanning(Some(ActionState::Pending)),
             DirSearch::Finished(counter, found) => {
@@ -199,8 +929,116 @@ impl App {
                 self.search_counter = counter;
                 self.search_results = found;
             }
-            DirSearch::Found(e, size) => self.list.push(DirEntryItem::from_entry(e, size)),
+            DirSearch::Found(mut item) => {
+                // Watching finds again what was deleted and reinstalled, or moved back.
+                let path = item.entry.path().to_path_buf();
+                self.list
+                    .remove_where(|item| item.entry.path() == path && !item.is_deleting());
+                if self.kept_selection.remove(&path) {
+                    item.set_is_on(true);
+                }
+                match item.workspace.clone() {
+                    Some(workspace) => self.add_to_workspace(*item, workspace),
+                    None => self.list.push(*item),
+                }
+                self.refresh_view();
+            }
+            DirSearch::Sized(path, size) => {
+                self.list.mutate_where(
+                    |item| item.entry.path() == path || item.has_member(&path),
+                    |item| {
+                        // A tree removed while it was being sized fails to size, keep it as is.
+                        if item.delete_state.is_some() {
+                            return;
+                        }
+                        if item.is_group() {
+                            item.mutate_member(&path, |member| member.set_size(size));
+                            return;
+                        }
+                        let resized = !item.is_sizing();
+                        item.set_size(size);
+                        // Sized again after a change, the breakdown is out of date. One asked
+                        // for while the first sizing went on is still good.
+                        if resized && item.breakdown_state.is_some() {
+                            item.expanded = false;
+                            item.breakdown = None;
+                            item.breakdown_state = None;
+                        }
+                    },
+                );
+                self.refresh_view();
+            }
+            DirSearch::Changed(changed) => {
+                let path = changed.entry.path().to_path_buf();
+                let update = |item: &mut DirEntryItem| {
+                    if item.paths != changed.paths {
+                        item.paths = changed.paths.clone();
+                        item.size_state = ActionState::Pending;
+                        item.expanded = false;
+                        item.breakdown = None;
+                        item.breakdown_state = None;
+                    }
+                    item.project = changed.project.clone();
+                    item.package_manager = changed.package_manager;
+                };
+                self.list.mutate_where(
+                    |item| item.entry.path() == path || item.has_member(&path),
+                    |item| {
+                        if item.delete_state.is_some() {
+                            return;
+                        }
+                        if item.is_group() {
+                            item.mutate_member(&path, update);
+                        } else {
+                            update(item);
+                        }
+                    },
+                );
+                self.refresh_view();
+            }
+            DirSearch::Activity(path, last_active) => {
+                self.list.mutate_where(
+                    |item| item.entry.path() == path || item.has_member(&path),
+                    |item| {
+                        if item.is_group() {
+                            item.mutate_member(&path, |member| member.last_active = last_active);
+                        } else {
+                            item.last_active = last_active;
+                        }
+                    },
+                );
+                self.refresh_view();
+            }
             DirSearch::Progress(counter) => self.search_counter = counter,
+            DirSearch::MountSkipped(_) => self.skipped_mounts += 1,
+            DirSearch::Error(path, message) => self.scan_errors.push((path, message)),
+            DirSearch::IgnoreFile(path) => self.ignore_files.push(path),
+            DirSearch::Quarantine(path) => self.delete_queue.quarantine().sweep(path),
+            DirSearch::Breakdown(path, breakdown) => {
+                self.list.mutate_where(
+                    |item| item.entry.path() == path,
+                    |item| match breakdown {
+                        Ok(breakdown) => {
+                            item.breakdown = Some(Arc::new(breakdown));
+                            item.breakdown_state = Some(ActionState::Done);
+                        }
+                        Err(e) => item.breakdown_state = Some(ActionState::Failed(e)),
+                    },
+                );
+            }
+            DirSearch::Removed(path) => {
+                // Our own deletions are reported as such, keep them in the list.
+                self.list.mutate_each(
+                    |item| item.has_member(&path) && item.delete_state.is_none(),
+                    |group| group.remove_member(&path),
+                );
+                self.list.remove_where(|item| {
+                    item.delete_state.is_none()
+                        && (item.entry.path() == path
+                            || (item.is_group() && item.members.is_empty()))
+                });
+                self.refresh_view();
+            }
         }
     }
     pub fn handle_delete(&mut self, d: DirDelete) {
@@ -209,35 +1047,101 @@ impl App {
                 self.list.mutate_where(
                     |item| item.entry.path() == path,
                     |item| {
-                        self.deleting_size.add(item.size);
-                        item.delete_state = Some(ActionState::Pending)
+                        item.delete_state = Some(ActionState::Pending);
+                        item.permanent = None;
+                    },
+                );
+            }
+            DirDelete::Permanent(path, reason) => {
+                self.list.mutate_where(
+                    |item| item.entry.path() == path,
+                    |item| item.permanent = Some(reason),
+                );
+            }
+            DirDelete::Progress(path, bytes, files) => {
+                let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
+                self.list.mutate_where(
+                    |item| item.entry.path() == path,
+                    |item| {
+                        let freed = bytes.saturating_sub(item.removed.bytes);
+                        deletion.freed = deletion.freed.saturating_add(freed);
+                        item.removed = Removed { bytes, files };
                     },
                 );
             }
+            DirDelete::Deleted(path) if self.dry_running.remove(&path) => {
+                // Nothing was removed, the item can still be deleted for real.
+                let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
+                self.list.mutate_where(
+                    |item| item.entry.path() == path,
+                    |item| {
+                        self.deleting_size.finished(item.exclusive_size);
+                        let rest = item.exclusive_size.saturating_sub(item.removed.bytes);
+                        deletion.freed = deletion.freed.saturating_add(rest);
+                        // A group removes its members' directories, not the repository.
+                        self.dry_run_report
+                            .push((item.paths.clone(), item.exclusive_size));
+                        item.delete_state = None;
+                    },
+                );
+                if self.dry_running.is_empty() {
+                    self.dry_run_report.sort_by_key(|(_, size)| Reverse(*size));
+                    self.show_report = true;
+                }
+            }
             DirDelete::Deleted(path) => {
+                let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
                 self.list.mutate_where(
                     |item| item.entry.path() == path,
                     |item| {
-                        self.deleting_size.finished(item.size);
+                        self.deleting_size.finished(item.exclusive_size);
+                        let rest = item.exclusive_size.saturating_sub(item.removed.bytes);
+                        deletion.freed = deletion.freed.saturating_add(rest);
                         item.delete_state = Some(ActionState::Done)
                     },
                 );
             }
-            DirDelete::Failed(path, error_message) => {
+            DirDelete::Cancelled(path) => {
+                self.dry_running.remove(&path);
+                let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
+                self.list.mutate_where(
+                    |item| item.entry.path() == path,
+                    |item| {
+                        self.deleting_size.cancelled(item.exclusive_size);
+                        let rest = item.exclusive_size.saturating_sub(item.removed.bytes);
+                        deletion.total = deletion.total.saturating_sub(rest);
+                        // Untouched items can simply be deleted again later.
+                        item.delete_state = if item.removed == Removed::default() {
+                            None
+                        } else {
+                            Some(ActionState::PartiallyDeleted)
+                        };
+                    },
+                );
+            }
+            DirDelete::Failed(path, failure) => {
+                // What wasn't freed won't be.
+                let deletion = self.deletion.get_or_insert_with(DeletionProgress::new);
                 self.list.mutate_where(
                     |item| item.entry.path() == path,
                     |item| {
-                        self.deleting_size.failed(item.size);
-                        item.delete_state = Some(ActionState::Failed(error_message));
+                        self.deleting_size.failed(item.exclusive_size);
+                        let rest = item.exclusive_size.saturating_sub(item.removed.bytes);
+                        deletion.total = deletion.total.saturating_sub(rest);
+                        item.delete_state =
+                            Some(ActionState::Failed(failure.kind.label().to_string()));
                     },
                 );
+                self.delete_failures.push((path, failure));
             }
         }
+        self.refresh_selected();
     }
     pub fn append_filter_input(&mut self, c: char) {
         self.filter_input
             .get_or_insert_with(Default::default)
             .push(c);
+        self.refresh_view();
     }
 
     pub fn delete_filter_input(&mut self) {
@@ -249,5 +1153,71 @@ impl App {
                 self.is_in_search_mode = false;
             }
         }
+        self.refresh_view();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_entry_item::dir_entry;
+
+    const PAGE: u64 = 4096;
+
+    fn item(dir: &str, shared: &[(Inode, LinkedFile)]) -> DirEntryItem {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        let entry = dir_entry(&path).expect("test directory missing");
+        let mut item = DirEntryItem::from_entry(path, entry, "Node.js".to_string());
+        item.shared_files = shared.into();
+        item
+    }
+
+    /// A file with `nlink` links in all, `links` of them in the item.
+    fn linked(inode: u64, nlink: u64, links: u64) -> (Inode, LinkedFile) {
+        let file = LinkedFile {
+            allocated: PAGE,
+            apparent: PAGE,
+            nlink,
+            links,
+        };
+        ((1, inode), file)
+    }
+
+    #[test]
+    fn link_tally_counts_a_shared_file_once_both_links_are_selected() {
+        let a = item("src", &[linked(10, 2, 1)]);
+        let b = item(".", &[linked(10, 2, 1)]);
+        let mut tally = LinkTally::default();
+        assert_eq!(tally.update([&a].into_iter()), 0);
+        assert_eq!(tally.update([&a, &b].into_iter()), PAGE);
+        // Updating again with nothing changed counts nothing twice.
+        assert_eq!(tally.update([&a, &b].into_iter()), PAGE);
+    }
+
+    #[test]
+    fn link_tally_drops_a_file_once_one_of_its_links_is_deselected() {
+        let a = item("src", &[linked(10, 2, 1), linked(11, 3, 1)]);
+        let b = item(".", &[linked(10, 2, 1), linked(11, 3, 2)]);
+        let mut tally = LinkTally::default();
+        assert_eq!(tally.update([&a, &b].into_iter()), 2 * PAGE);
+        assert_eq!(tally.update([&b].into_iter()), 0);
+        assert_eq!(tally.update(std::iter::empty()), 0);
+        assert!(tally.files.is_empty());
+        assert!(tally.items.is_empty());
+    }
+
+    #[test]
+    fn link_tally_recounts_an_item_sized_again() {
+        let mut a = item("src", &[linked(10, 2, 1)]);
+        let b = item(".", &[linked(10, 2, 1), linked(11, 2, 1)]);
+        let mut tally = LinkTally::default();
+        assert_eq!(tally.update([&a, &b].into_iter()), PAGE);
+        // Sized again, the item now holds the other file's second link instead.
+        a.shared_files = [linked(11, 2, 1)].as_slice().into();
+        assert_eq!(tally.update([&a, &b].into_iter()), PAGE);
+        a.shared_files = [linked(10, 2, 1), linked(11, 2, 1)].as_slice().into();
+        assert_eq!(tally.update([&a, &b].into_iter()), 2 * PAGE);
+        a.shared_files = Arc::new([]);
+        assert_eq!(tally.update([&a, &b].into_iter()), 0);
     }
 }
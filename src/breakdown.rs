@@ -0,0 +1,122 @@
+use crate::dir_size::paths_size;
+use crate::scan_control::ScanControl;
+use rayon::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A part of a candidate and the space it takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartSize {
+    /// Path relative to the candidate, like `@next/swc-linux-x64-gnu` or
+    /// `webpack/node_modules`.
+    pub name: PathBuf,
+    pub size: u64,
+}
+
+/// What a candidate is made of, largest parts first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakdown {
+    /// Top-level entries. A `node_modules` scope like `@babel` counts as the packages in it.
+    pub packages: Vec<PartSize>,
+    /// `node_modules` directories anywhere below the top level. Those inside another
+    /// nested one are part of it.
+    pub nested: Vec<PartSize>,
+}
+
+/// Name of `path` relative to `candidate`, or its file name when it's outside of it.
+fn relative_name(candidate: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(candidate)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.file_name().map(PathBuf::from).unwrap_or_default())
+}
+
+/// The top-level entries of `path`, or `path` itself when it's a file.
+fn top_level(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !fs::symlink_metadata(path)?.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let in_node_modules = path.file_name().is_some_and(|name| name == "node_modules");
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let is_scope = in_node_modules
+            && entry.file_name().to_string_lossy().starts_with('@')
+            && entry.file_type()?.is_dir();
+        if is_scope {
+            for package in fs::read_dir(entry.path())? {
+                entries.push(package?.path());
+            }
+        } else {
+            entries.push(entry.path());
+        }
+    }
+    Ok(entries)
+}
+
+/// The outermost `node_modules` directories below the top level of `path`.
+///
+/// Like sizing the parts, a directory that can't be read fails the whole breakdown, which
+/// shows why, rather than leaving its nested `node_modules` out.
+fn nested_node_modules(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut nested = Vec::new();
+    let mut walker = WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = entry.map_err(|e| match e.io_error() {
+            Some(io_error) => io::Error::new(
+                io_error.kind(),
+                format!("{}: {}", e.path().unwrap_or(path).display(), io_error),
+            ),
+            // A symlink loop, which isn't followed anyway.
+            None => io::Error::other(e.to_string()),
+        })?;
+        if entry.file_type().is_dir() && entry.file_name() == "node_modules" {
+            walker.skip_current_dir();
+            nested.push(entry.into_path());
+        }
+    }
+    Ok(nested)
+}
+
+/// Sizes `paths` in parallel, largest first.
+fn sizes(
+    candidate: &Path,
+    paths: Vec<PathBuf>,
+    control: &ScanControl,
+) -> io::Result<Vec<PartSize>> {
+    let mut sizes = paths
+        .into_par_iter()
+        .map(|path| {
+            Ok(PartSize {
+                name: relative_name(candidate, &path),
+                size: paths_size(std::slice::from_ref(&path), control)?.allocated,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    sizes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    Ok(sizes)
+}
+
+/// Breaks down the candidate at `candidate`, made of `paths`.
+///
+/// Sizing the parts waits while `control` is paused, and gives up once it's cancelled.
+pub fn breakdown(
+    candidate: &Path,
+    paths: &[PathBuf],
+    control: &ScanControl,
+) -> io::Result<Breakdown> {
+    let mut packages = Vec::new();
+    let mut nested = Vec::new();
+    for path in paths {
+        packages.extend(top_level(path)?);
+        nested.extend(nested_node_modules(path)?);
+    }
+    Ok(Breakdown {
+        packages: sizes(candidate, packages, control)?,
+        nested: sizes(candidate, nested, control)?,
+    })
+}
@@ -0,0 +1,174 @@
+use crate::app::AppResult;
+use crate::config::Config;
+use crate::deleter::DEFAULT_JOBS;
+use crate::effects::{DeleteOptions, ScanOptions};
+use crate::exclude::Excludes;
+use crate::targets::{Targets, DEFAULT_TARGETS};
+use clap::Parser;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Find and delete `node_modules` and other build artifact directories.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Directories to scan. Defaults to the current directory.
+    #[arg(value_name = "ROOT", default_value = ".")]
+    pub roots: Vec<PathBuf>,
+
+    /// Profiles to look for, by name or ecosystem (e.g. `node,rust,python`).
+    #[arg(short, long = "target", value_name = "PROFILE", value_delimiter = ',')]
+    pub targets: Vec<String>,
+
+    /// Skip directories matching this glob. Can be given several times.
+    #[arg(short, long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Don't look deeper than this many directories below each root.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Don't cross into other filesystems, like NFS or FUSE mounts.
+    #[arg(short = 'x', long)]
+    pub one_file_system: bool,
+
+    /// Keep watching the roots and update the list as directories come and go (Linux only)
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// Walk the roots again instead of restoring the last scan from the index.
+    #[arg(long)]
+    pub rescan: bool,
+
+    /// Go through deletions without removing anything, and report what would be freed.
+    /// Can also be toggled from the list.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Move directories to the trash instead of deleting them for good (Unix only).
+    #[arg(long)]
+    pub trash: bool,
+
+    /// Rename directories into a hidden quarantine next to them, and purge them in the
+    /// background once they can no longer be restored.
+    #[arg(long, conflicts_with = "trash")]
+    pub quarantine: bool,
+
+    /// Directories deleted at the same time [default: 4]
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..=64))]
+    pub jobs: Option<u16>,
+
+    /// Show project names from `package.json` instead of paths.
+    #[arg(long)]
+    pub project_names: bool,
+
+    /// Path of the config file.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Resolves the roots given on the command line into the list of directories to walk.
+    ///
+    /// Each root is `~`-expanded and canonicalized. Roots nested inside another root are
+    /// dropped so the same directory is never reported twice.
+    pub fn scan_roots(&self) -> AppResult<Vec<PathBuf>> {
+        let mut roots: Vec<PathBuf> = Vec::with_capacity(self.roots.len());
+        for root in &self.roots {
+            let expanded = expand_tilde(root);
+            let canonical = expanded
+                .canonicalize()
+                .map_err(|e| format!("{}: {}", expanded.display(), e))?;
+            if !canonical.is_dir() {
+                return Err(format!("{}: not a directory", expanded.display()).into());
+            }
+            roots.push(canonical);
+        }
+        roots.sort();
+        roots.dedup();
+        let nested: Vec<PathBuf> = roots
+            .iter()
+            .filter(|root| {
+                roots
+                    .iter()
+                    .any(|other| other != *root && root.starts_with(other))
+            })
+            .cloned()
+            .collect();
+        roots.retain(|root| !nested.contains(root));
+        Ok(roots)
+    }
+
+    /// Resolves the target profiles, preferring the command line over the config.
+    pub fn targets(&self, config: &Config) -> AppResult<Targets> {
+        let names = if !self.targets.is_empty() {
+            self.targets.clone()
+        } else if !config.targets.is_empty() {
+            config.targets.clone()
+        } else {
+            DEFAULT_TARGETS
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        };
+        Targets::resolve(&names, &config.profiles)
+    }
+
+    /// Gathers everything the walker needs from the command line and the config.
+    pub fn scan_options(&self, config: &Config) -> AppResult<ScanOptions> {
+        if self.watch && !cfg!(target_os = "linux") {
+            return Err("--watch relies on inotify and is only available on Linux".into());
+        }
+        let excludes: Vec<String> = config
+            .exclude
+            .iter()
+            .chain(self.exclude.iter())
+            .cloned()
+            .collect();
+        Ok(ScanOptions {
+            roots: self.scan_roots()?,
+            targets: self.targets(config)?,
+            excludes: Excludes::new(&excludes)?,
+            max_depth: self.max_depth,
+            one_file_system: self.one_file_system,
+            cached: HashMap::new(),
+            watch: self.watch,
+        })
+    }
+
+    /// How many directories to delete at the same time, preferring the command line over
+    /// the config.
+    pub fn delete_jobs(&self, config: &Config) -> usize {
+        self.jobs
+            .map(usize::from)
+            .or(config.jobs)
+            .unwrap_or(DEFAULT_JOBS)
+    }
+
+    /// How to delete, from the command line and the config.
+    pub fn delete_options(&self, config: &Config) -> AppResult<DeleteOptions> {
+        let trash = self.trash || config.trash;
+        if trash && !cfg!(unix) {
+            return Err("--trash is only available on Unix".into());
+        }
+        let quarantine = self.quarantine || config.quarantine.enabled;
+        if trash && quarantine {
+            return Err("moving to the trash and quarantining can't be used together".into());
+        }
+        Ok(DeleteOptions {
+            dry_run: self.dry_run,
+            trash,
+            quarantine,
+        })
+    }
+}
+
+/// Expands a leading `~` to the current user's home directory.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
+}
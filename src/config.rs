@@ -0,0 +1,269 @@
+use crate::app::AppResult;
+use crate::quarantine::DEFAULT_GRACE;
+use crate::targets::TargetProfile;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Settings read from `$XDG_CONFIG_HOME/delete-node-modules/config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Profiles to scan for when none are given on the command line.
+    pub targets: Vec<String>,
+    /// Additional profiles, on top of the built-in ones.
+    pub profiles: Vec<TargetProfile>,
+    /// Globs for directories to skip, on top of the ones given on the command line.
+    pub exclude: Vec<String>,
+    /// Move to the trash instead of deleting for good.
+    pub trash: bool,
+    /// Directories deleted at the same time.
+    pub jobs: Option<usize>,
+    /// Selections past which deleting asks to type `delete`.
+    pub confirm: ConfirmThreshold,
+    /// Renaming into quarantine first, and purging later.
+    pub quarantine: QuarantineConfig,
+}
+
+/// Selections past which deleting asks to type `delete` rather than just confirm.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfirmThreshold {
+    /// Number of directories.
+    pub count: usize,
+    /// Space freed, in bytes or like `"10 GB"`.
+    #[serde(deserialize_with = "deserialize_size")]
+    pub size: u64,
+}
+
+impl Default for ConfirmThreshold {
+    fn default() -> Self {
+        ConfirmThreshold {
+            count: 20,
+            size: 10 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl ConfirmThreshold {
+    pub fn is_exceeded(&self, count: usize, size: u64) -> bool {
+        count > self.count || size > self.size
+    }
+}
+
+/// Deleting in two steps, see [`Quarantine`](crate::quarantine::Quarantine).
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuarantineConfig {
+    /// Quarantine instead of deleting right away.
+    pub enabled: bool,
+    /// How long quarantined directories can be restored, in seconds or like `"10m"`.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub grace: Duration,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        QuarantineConfig {
+            enabled: false,
+            grace: DEFAULT_GRACE,
+        }
+    }
+}
+
+/// Parses sizes like `512`, `800 MB` or `1.5GB`. Units are powers of 1024, as shown in
+/// the list.
+fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size `{}`", text))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size unit in `{}`", text)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+fn deserialize_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(bytes),
+        Size::Text(text) => parse_size(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parses durations like `90`, `30s`, `10m` or `1.5h`. A bare number is in seconds.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`", text))?;
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "s" | "sec" | "secs" => 1.0,
+        "m" | "min" | "mins" => 60.0,
+        "h" | "hour" | "hours" => 60.0 * 60.0,
+        "d" | "day" | "days" => 24.0 * 60.0 * 60.0,
+        _ => return Err(format!("invalid duration unit in `{}`", text)),
+    };
+    Duration::try_from_secs_f64(number * multiplier).map_err(|e| format!("`{}`: {}", text, e))
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Length {
+        Seconds(u64),
+        Text(String),
+    }
+    match Length::deserialize(deserializer)? {
+        Length::Seconds(seconds) => Ok(Duration::from_secs(seconds)),
+        Length::Text(text) => parse_duration(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Config {
+    /// The default location of the config file.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("delete-node-modules").join("config.toml"))
+    }
+
+    /// Loads the config at `path`, or the default location when `path` is `None`.
+    ///
+    /// A missing file at the default location yields the default config, a missing file
+    /// that was asked for explicitly is an error.
+    pub fn load(path: Option<&Path>) -> AppResult<Config> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_reads_bytes() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size(" 512 b "), Ok(512));
+    }
+
+    #[test]
+    fn parse_size_reads_units() {
+        assert_eq!(parse_size("1K"), Ok(1 << 10));
+        assert_eq!(parse_size("1kb"), Ok(1 << 10));
+        assert_eq!(parse_size("1KiB"), Ok(1 << 10));
+        assert_eq!(parse_size("2M"), Ok(2 << 20));
+        assert_eq!(parse_size("2 MB"), Ok(2 << 20));
+        assert_eq!(parse_size("3G"), Ok(3 << 30));
+        assert_eq!(parse_size("3gib"), Ok(3 << 30));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+    }
+
+    #[test]
+    fn parse_size_reads_decimals() {
+        assert_eq!(parse_size("1.5K"), Ok(1536));
+        assert_eq!(parse_size("0.5G"), Ok(1 << 29));
+        assert_eq!(parse_size(".5M"), Ok(1 << 19));
+        // Fractions of a byte are dropped.
+        assert_eq!(parse_size("1.9"), Ok(1));
+    }
+
+    #[test]
+    fn parse_size_rejects_empty() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("   ").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn parse_size_rejects_garbage() {
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("-1G").is_err());
+        assert!(parse_size("1.2.3M").is_err());
+        assert!(parse_size("1X").is_err());
+        assert!(parse_size("1GB extra").is_err());
+        assert!(parse_size("1 2").is_err());
+    }
+
+    #[test]
+    fn parse_duration_reads_seconds() {
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 90 secs "), Ok(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn parse_duration_reads_units() {
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("10 MIN"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1 hour"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("2days"), Ok(Duration::from_secs(172800)));
+    }
+
+    #[test]
+    fn parse_duration_reads_decimals() {
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(".5h"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("0.25"), Ok(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_empty() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("   ").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_garbage() {
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("-5m").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+        assert!(parse_duration("5w").is_err());
+        assert!(parse_duration("5m later").is_err());
+        assert!(parse_duration("99999999999999999999999d").is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::dir_entry_item::DirEntryItem;
+use crate::dir_size::freed_bytes;
+use crate::effects::DeleteOptions;
+use crate::event::{DirDelete, Event};
+use crate::failure::DeleteFailure;
+use crate::quarantine::Quarantine;
+#[cfg(unix)]
+use crate::trash;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// How often a deletion reports its progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a deletion removed so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Removed {
+    /// Allocated bytes freed.
+    pub bytes: u64,
+    /// Non-directory entries removed.
+    pub files: u64,
+}
+
+/// Why a removal stopped.
+#[derive(Debug)]
+pub enum RemoveError {
+    /// Asked to stop, kept apart from errors so an `EINTR` never looks like one.
+    Cancelled,
+    /// An entry that couldn't be removed, and why.
+    Failed { path: PathBuf, error: io::Error },
+}
+
+impl RemoveError {
+    fn at(path: &Path) -> impl FnOnce(io::Error) -> RemoveError + '_ {
+        move |error| RemoveError::Failed {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+}
+
+/// Removes `path`, whether it's a tree, a single file or a symlink, adding what goes to
+/// `removed`.
+///
+/// The tree is removed entry by entry, deepest first, and `progress` is called along
+/// the way, at most every [`PROGRESS_INTERVAL`]. `proceed` is asked before each entry,
+/// the removal stops with [`RemoveError::Cancelled`] when it says no. Errors tell the
+/// entry that couldn't be removed.
+pub fn remove_path(
+    path: &Path,
+    removed: &mut Removed,
+    progress: &mut dyn FnMut(&Removed),
+    proceed: &dyn Fn() -> bool,
+) -> Result<(), RemoveError> {
+    let mut reported = Instant::now();
+    // A symlink is removed itself, even at the root, never what it points to.
+    let walker = WalkDir::new(path)
+        .follow_links(false)
+        .follow_root_links(false)
+        .contents_first(true);
+    for entry in walker {
+        if !proceed() {
+            return Err(RemoveError::Cancelled);
+        }
+        let entry = entry.map_err(|e| RemoveError::Failed {
+            path: e.path().unwrap_or(path).to_path_buf(),
+            error: e.into(),
+        })?;
+        let metadata = entry.metadata().map_err(|e| RemoveError::Failed {
+            path: entry.path().to_path_buf(),
+            error: e.into(),
+        })?;
+        if entry.file_type().is_dir() {
+            fs::remove_dir(entry.path()).map_err(RemoveError::at(entry.path()))?;
+        } else {
+            fs::remove_file(entry.path()).map_err(RemoveError::at(entry.path()))?;
+            removed.files = removed.files.saturating_add(1);
+        }
+        removed.bytes = removed.bytes.saturating_add(freed_bytes(&metadata));
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            progress(removed);
+            reported = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+/// Deletes `path`, part of the list entry `item`, as `options` say. Without a trash on
+/// the filesystem of `path`, or a quarantine when `path` is a mount point, it is deleted
+/// for good, telling why through `send`.
+fn delete_path(
+    item: &Path,
+    path: &Path,
+    options: DeleteOptions,
+    quarantine: &Quarantine,
+    removed: &mut Removed,
+    send: &dyn Fn(DirDelete),
+    proceed: &dyn Fn() -> bool,
+) -> Result<(), RemoveError> {
+    let permanent = |reason: &str| send(DirDelete::Permanent(item.into(), reason.to_string()));
+    if options.quarantine {
+        match quarantine.isolate(item, path) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                permanent("a mount point can't be quarantined")
+            }
+            result => return result.map_err(RemoveError::at(path)),
+        }
+    }
+    #[cfg(unix)]
+    if options.trash {
+        match trash::move_to_trash(path) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                permanent("no usable trash on this filesystem")
+            }
+            result => return result.map_err(RemoveError::at(path)),
+        }
+    }
+    let mut progress = |removed: &Removed| {
+        send(DirDelete::Progress(
+            item.into(),
+            removed.bytes,
+            removed.files,
+        ))
+    };
+    remove_path(path, removed, &mut progress, proceed)
+}
+
+/// Deletes `item`, reporting how it goes through `sender`, until `proceed` says no.
+fn delete_item(
+    item: &DirEntryItem,
+    options: DeleteOptions,
+    quarantine: &Quarantine,
+    sender: &Sender<Event>,
+    proceed: &dyn Fn() -> bool,
+) {
+    let send = |event: DirDelete| {
+        sender
+            .send(Event::Delete(event))
+            .expect("Unable to send data through the channel.");
+    };
+    send(DirDelete::Deleting(item.entry.path().into()));
+    let mut removed = Removed::default();
+    let progress = |removed: &Removed| {
+        send(DirDelete::Progress(
+            item.entry.path().into(),
+            removed.bytes,
+            removed.files,
+        ))
+    };
+    let result = if options.dry_run {
+        Ok(())
+    } else {
+        item.paths.iter().try_for_each(|path| {
+            delete_path(
+                item.entry.path(),
+                path,
+                options,
+                quarantine,
+                &mut removed,
+                &send,
+                proceed,
+            )
+            .map_err(|e| (path, e))
+        })
+    };
+    match result {
+        Ok(_) => send(DirDelete::Deleted(item.entry.path().into())),
+        Err((_, RemoveError::Cancelled)) => {
+            // The last progress may be behind, it tells what is left of the item.
+            progress(&removed);
+            send(DirDelete::Cancelled(item.entry.path().into()));
+        }
+        Err((tree, RemoveError::Failed { path, error })) => {
+            progress(&removed);
+            send(DirDelete::Failed(
+                item.entry.path().into(),
+                DeleteFailure::new(tree, &path, &error),
+            ))
+        }
+    }
+}
+
+/// Directories deleted at the same time, unless configured otherwise.
+pub const DEFAULT_JOBS: usize = 4;
+
+/// The most directories deleted at the same time.
+pub const MAX_JOBS: usize = 64;
+
+/// An item waiting to be deleted.
+#[derive(Debug)]
+struct Job {
+    item: DirEntryItem,
+    options: DeleteOptions,
+    sender: Sender<Event>,
+    /// Cancelling moves the queue to the next generation, stopping the jobs of the
+    /// previous ones.
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct QueueState {
+    jobs: VecDeque<Job>,
+    /// Jobs being worked on.
+    running: usize,
+    /// Worker threads started so far.
+    workers: usize,
+    /// How many jobs may run at the same time.
+    jobs_limit: usize,
+}
+
+/// Deletes items on workers of its own, a bounded number at a time.
+///
+/// Deleting many trees at once thrashes spinning disks and network filesystems. The
+/// limit can change while deletions go on: more workers are started when it grows,
+/// extra ones wait for their turn when it shrinks.
+///
+/// The queue can be paused and cancelled, workers check it between two entries.
+#[derive(Debug)]
+pub struct DeleteQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    paused: AtomicBool,
+    generation: AtomicU64,
+    /// Where quarantining deletions put items.
+    quarantine: Arc<Quarantine>,
+}
+
+impl Default for DeleteQueue {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+impl DeleteQueue {
+    pub fn new(quarantine: Arc<Quarantine>) -> Self {
+        DeleteQueue {
+            state: Mutex::new(QueueState {
+                jobs: VecDeque::new(),
+                running: 0,
+                workers: 0,
+                jobs_limit: DEFAULT_JOBS,
+            }),
+            changed: Condvar::new(),
+            paused: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+            quarantine,
+        }
+    }
+
+    pub fn quarantine(&self) -> &Quarantine {
+        &self.quarantine
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().expect("Delete queue lock poisoned.")
+    }
+
+    /// Queues `items`, reporting how their deletion goes through `sender`.
+    pub fn push(
+        self: &Arc<Self>,
+        items: Vec<DirEntryItem>,
+        options: DeleteOptions,
+        sender: &Sender<Event>,
+    ) {
+        let mut state = self.lock();
+        let generation = self.generation.load(Ordering::SeqCst);
+        state.jobs.extend(items.into_iter().map(|item| Job {
+            item,
+            options,
+            sender: sender.clone(),
+            generation,
+        }));
+        self.spawn_workers(&mut state);
+        self.changed.notify_all();
+    }
+
+    /// Sets how many items may be deleted at the same time, between 1 and [`MAX_JOBS`].
+    pub fn set_jobs_limit(self: &Arc<Self>, limit: usize) {
+        let mut state = self.lock();
+        state.jobs_limit = limit.clamp(1, MAX_JOBS);
+        // Workers are only started once there is something to delete.
+        if state.workers > 0 {
+            self.spawn_workers(&mut state);
+        }
+        self.changed.notify_all();
+    }
+
+    pub fn jobs_limit(&self) -> usize {
+        self.lock().jobs_limit
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        let _state = self.lock();
+        self.paused.store(false, Ordering::SeqCst);
+        self.changed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stops the items being deleted after the entry being removed, and drops the
+    /// queued ones, whose paths are returned. The queue is resumed if it was paused.
+    pub fn cancel(&self) -> Vec<PathBuf> {
+        let mut state = self.lock();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.paused.store(false, Ordering::SeqCst);
+        let dropped = state
+            .jobs
+            .drain(..)
+            .map(|job| job.item.entry.path().to_path_buf())
+            .collect();
+        self.changed.notify_all();
+        dropped
+    }
+
+    /// Blocks while the queue is paused, then tells whether a job of `generation` should
+    /// go on.
+    fn proceed(&self, generation: u64) -> bool {
+        let current = || self.generation.load(Ordering::SeqCst) == generation;
+        if self.is_paused() {
+            let state = self.lock();
+            let _state = self
+                .changed
+                .wait_while(state, |_| self.is_paused() && current())
+                .expect("Delete queue lock poisoned.");
+        }
+        current()
+    }
+
+    /// Items waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.lock().jobs.len()
+    }
+
+    /// Items being deleted.
+    pub fn running(&self) -> usize {
+        self.lock().running
+    }
+
+    fn spawn_workers(self: &Arc<Self>, state: &mut QueueState) {
+        while state.workers < state.jobs_limit {
+            state.workers += 1;
+            let queue = Arc::clone(self);
+            thread::spawn(move || queue.work());
+        }
+    }
+
+    fn work(&self) {
+        loop {
+            let job = {
+                let state = self.lock();
+                let mut state = self
+                    .changed
+                    .wait_while(state, |state| {
+                        state.jobs.is_empty()
+                            || state.running >= state.jobs_limit
+                            || self.is_paused()
+                    })
+                    .expect("Delete queue lock poisoned.");
+                state.running += 1;
+                state.jobs.pop_front().expect("Waited for a job.")
+            };
+            delete_item(
+                &job.item,
+                job.options,
+                &self.quarantine,
+                &job.sender,
+                &|| self.proceed(job.generation),
+            );
+            self.lock().running -= 1;
+            self.changed.notify_all();
+        }
+    }
+}
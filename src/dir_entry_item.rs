@@ -1,34 +1,298 @@
-use walkdir::DirEntry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use walkdir::{DirEntry, WalkDir};
 use crate::actions::ActionState;
+use crate::breakdown::Breakdown;
+use crate::deleter::Removed;
+use crate::dir_size::{DirSize, Inode, LinkedFile};
 use crate::list::{Toggle, Deletable};
+use crate::package_manager::PackageManager;
+use crate::project::Project;
+use crate::workspace::Workspace;
 
 #[derive(Debug, Clone)]
 pub struct DirEntryItem {
+    /// The scan root this entry was found under.
+    pub root: PathBuf,
     pub entry: DirEntry,
+    /// Label of the target profile the entry matched, e.g. `Rust`.
+    pub ecosystem: String,
+    /// What deleting the entry removes, usually just the entry itself.
+    pub paths: Vec<PathBuf>,
+    /// The manager that installed a Node.js entry.
+    pub package_manager: Option<PackageManager>,
+    /// The project owning the entry, from the `package.json` next to it.
+    pub project: Option<Project>,
+    /// When the owning project was last worked on, once known.
+    pub last_active: Option<SystemTime>,
+    /// The entry's mtime when it was found.
+    pub modified: Option<SystemTime>,
+    /// Allocated bytes on disk.
     pub size: u64,
+    pub apparent_size: u64,
+    pub files: u64,
+    /// Allocated bytes freed by deleting this entry alone.
+    pub exclusive_size: u64,
+    /// Hard-linked files that also have links outside this entry.
+    pub shared_files: Arc<[(Inode, LinkedFile)]>,
+    pub size_state: ActionState,
     pub delete_state: Option<ActionState>,
+    /// What deleting the entry removed so far.
+    pub removed: Removed,
+    /// Why deleting the entry removes it for good rather than as asked.
+    pub permanent: Option<String>,
+    /// Show the breakdown under the entry.
+    pub expanded: bool,
+    /// What the entry is made of, computed when first expanded.
+    pub breakdown: Option<Arc<Breakdown>>,
+    pub breakdown_state: Option<ActionState>,
+    /// The monorepo the entry's project is a package of.
+    pub workspace: Option<Arc<Workspace>>,
+    /// For the entry standing for a whole workspace, the `node_modules` of its packages.
+    pub members: Vec<DirEntryItem>,
     is_on: bool,
 }
 
+/// The `walkdir` entry for the directory at `path` itself.
+pub fn dir_entry(path: &Path) -> Option<DirEntry> {
+    WalkDir::new(path)
+        .max_depth(0)
+        .follow_links(false)
+        .into_iter()
+        .next()?
+        .ok()
+        .filter(|entry| entry.file_type().is_dir())
+}
+
 impl DirEntryItem {
-    pub fn from_entry(entry: DirEntry, size: u64) -> DirEntryItem {
+    pub fn from_entry(root: PathBuf, entry: DirEntry, ecosystem: String) -> DirEntryItem {
         DirEntryItem {
+            root,
+            paths: vec![entry.path().to_path_buf()],
             entry,
-            size,
+            ecosystem,
+            package_manager: None,
+            project: None,
+            last_active: None,
+            modified: None,
+            size: 0,
+            apparent_size: 0,
+            files: 0,
+            exclusive_size: 0,
+            shared_files: Arc::new([]),
+            size_state: ActionState::Pending,
             is_on: false,
             delete_state: None,
+            removed: Removed::default(),
+            permanent: None,
+            expanded: false,
+            breakdown: None,
+            breakdown_state: None,
+            workspace: None,
+            members: Vec::new(),
+        }
+    }
+
+    /// An entry standing for `workspace`, with `member` as its first package.
+    ///
+    /// Deleting it deletes every member. `None` if the workspace root is gone.
+    pub fn group(member: DirEntryItem, workspace: Arc<Workspace>) -> Option<DirEntryItem> {
+        let entry = dir_entry(&workspace.root)?;
+        let mut group =
+            DirEntryItem::from_entry(member.root.clone(), entry, member.ecosystem.clone());
+        group.project = Project::at(&workspace.root);
+        group.package_manager = member.package_manager;
+        group.workspace = Some(workspace);
+        group.members.push(member);
+        group.refresh_members();
+        Some(group)
+    }
+
+    /// Whether the entry stands for a workspace, even one whose members are all gone.
+    pub fn is_group(&self) -> bool {
+        self.workspace
+            .as_ref()
+            .is_some_and(|workspace| workspace.root == self.entry.path())
+    }
+
+    /// Whether `path` is one of the group's members.
+    pub fn has_member(&self, path: &Path) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.entry.path() == path)
+    }
+
+    /// Applies `mutator` to the member at `path`, and updates the group's totals.
+    pub fn mutate_member<F>(&mut self, path: &Path, mutator: F)
+    where
+        F: FnOnce(&mut DirEntryItem),
+    {
+        if let Some(member) = self
+            .members
+            .iter_mut()
+            .find(|member| member.entry.path() == path)
+        {
+            mutator(member);
+            self.refresh_members();
+        }
+    }
+
+    /// Adds `member`, replacing the one with the same path.
+    pub fn add_member(&mut self, member: DirEntryItem) {
+        self.members
+            .retain(|known| known.entry.path() != member.entry.path());
+        self.members.push(member);
+        self.members
+            .sort_by(|a, b| a.entry.path().cmp(b.entry.path()));
+        self.refresh_members();
+    }
+
+    /// Removes the member at `path`.
+    pub fn remove_member(&mut self, path: &Path) {
+        self.members.retain(|member| member.entry.path() != path);
+        self.refresh_members();
+    }
+
+    /// Derives the group's paths, sizes and activity from its members.
+    ///
+    /// Hard links between members are resolved the same way as within a tree.
+    fn refresh_members(&mut self) {
+        self.paths = self
+            .members
+            .iter()
+            .flat_map(|member| member.paths.iter().cloned())
+            .collect();
+        self.size = self.members.iter().map(|member| member.size).sum();
+        self.apparent_size = self.members.iter().map(|member| member.apparent_size).sum();
+        self.files = self.members.iter().map(|member| member.files).sum();
+        self.exclusive_size = self
+            .members
+            .iter()
+            .map(|member| member.exclusive_size)
+            .sum();
+        let mut linked: HashMap<Inode, LinkedFile> = HashMap::new();
+        for (inode, file) in self
+            .members
+            .iter()
+            .flat_map(|member| member.shared_files.iter())
+        {
+            linked
+                .entry(*inode)
+                .and_modify(|known| known.links += file.links)
+                .or_insert(*file);
+        }
+        // A file linked from several members is freed once they all go.
+        let mut shared = Vec::new();
+        for (inode, file) in linked {
+            if file.is_complete() {
+                self.exclusive_size = self.exclusive_size.saturating_add(file.allocated);
+            } else {
+                shared.push((inode, file));
+            }
+        }
+        self.shared_files = shared.into();
+        self.size_state = self
+            .members
+            .iter()
+            .find_map(|member| match &member.size_state {
+                ActionState::Done => None,
+                state => Some(state.clone()),
+            })
+            .unwrap_or(ActionState::Done);
+        self.last_active = self
+            .members
+            .iter()
+            .filter_map(|member| member.last_active)
+            .max();
+    }
+
+    /// The candidates the entry stands for: the members of a group, or the entry itself.
+    pub fn candidates(&self) -> Vec<&DirEntryItem> {
+        if self.is_group() {
+            self.members.iter().collect()
+        } else {
+            vec![self]
         }
     }
+
+    pub fn set_size(&mut self, size: Result<DirSize, String>) {
+        match size {
+            Ok(size) => {
+                self.size = size.allocated;
+                self.apparent_size = size.apparent;
+                self.files = size.files;
+                self.exclusive_size = size.exclusive;
+                self.shared_files = size.shared.into();
+                self.size_state = ActionState::Done;
+            }
+            Err(e) => self.size_state = ActionState::Failed(e),
+        }
+    }
+
+    /// Allocated bytes shared with other directories through hard links.
+    pub fn shared_size(&self) -> u64 {
+        self.size.saturating_sub(self.exclusive_size)
+    }
+
+    /// Whether the entry can be selected: not deleted yet, or only partially, to finish.
     pub fn can_toggle(&self) -> bool {
-        self.delete_state.is_none()
+        matches!(
+            self.delete_state,
+            None | Some(ActionState::PartiallyDeleted)
+        )
+    }
+
+    /// The directory of the owning project, or the entry's parent when there is none.
+    /// For a group, the workspace root.
+    pub fn project_root(&self) -> &Path {
+        match &self.project {
+            _ if self.is_group() => self.entry.path(),
+            Some(project) => &project.root,
+            None => self.entry.path().parent().unwrap_or(self.entry.path()),
+        }
+    }
+
+    /// Where to look for when the owning project was last worked on. An orphaned
+    /// `node_modules` has no project, its parent may be a home directory.
+    pub fn activity_root(&self) -> Option<&Path> {
+        (!self.is_orphaned()).then(|| self.project_root())
+    }
+
+    /// How long ago the owning project was last worked on, once known.
+    pub fn age(&self) -> Option<Duration> {
+        self.last_active
+            .map(|time| time.elapsed().unwrap_or(Duration::ZERO))
+    }
+
+    /// A `node_modules` without a `package.json` next to it.
+    pub fn is_orphaned(&self) -> bool {
+        self.project.is_none() && self.entry.file_name() == "node_modules"
+    }
+
+    pub fn is_sizing(&self) -> bool {
+        matches!(self.size_state, ActionState::Pending)
     }
 
     pub fn is_deleting(&self) -> bool {
-        matches!(self.delete_state, Some(ActionState::Pending))
+        matches!(
+            self.delete_state,
+            Some(ActionState::Queued | ActionState::Pending)
+        )
     }
     pub fn is_on(&self) -> bool {
         self.is_on
     }
+
+    /// The entry path relative to the root it was found under, `.` for the root itself.
+    pub fn relative_path(&self) -> &Path {
+        match self.entry.path().strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => Path::new("."),
+            Ok(relative) => relative,
+            Err(_) => self.entry.path(),
+        }
+    }
 }
 
 impl Toggle for DirEntryItem {
@@ -50,7 +314,11 @@ impl Toggle for DirEntryItem {
 }
 
 impl Deletable for DirEntryItem {
+    fn is_deletable(&self) -> bool {
+        self.can_toggle()
+    }
+
     fn can_delete(&self) -> bool {
-        self.is_on() && self.delete_state.is_none()
+        self.is_on() && self.is_deletable()
     }
 }
\ No newline at end of file
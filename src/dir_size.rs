@@ -0,0 +1,203 @@
+use crate::scan_control::ScanControl;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Identifies a file across directories, `(st_dev, st_ino)`.
+pub type Inode = (u64, u64);
+
+/// A file with more than one hard link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedFile {
+    pub allocated: u64,
+    pub apparent: u64,
+    /// Links to the file anywhere on its filesystem (`st_nlink`).
+    pub nlink: u64,
+    /// Links to the file found in the tree.
+    pub links: u64,
+}
+
+impl LinkedFile {
+    /// Whether every link to the file has been accounted for.
+    pub fn is_complete(&self) -> bool {
+        self.links >= self.nlink
+    }
+}
+
+/// Disk usage of a directory tree.
+///
+/// Hard-linked files are counted once. Those with links outside the tree are listed in
+/// `shared`, deleting the tree alone doesn't free them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirSize {
+    /// Bytes actually allocated on disk (`st_blocks * 512`).
+    pub allocated: u64,
+    /// Sum of the file lengths.
+    pub apparent: u64,
+    /// Number of non-directory entries.
+    pub files: u64,
+    /// Allocated bytes freed by deleting the tree.
+    pub exclusive: u64,
+    /// Files also linked from outside the tree.
+    pub shared: Vec<(Inode, LinkedFile)>,
+}
+
+/// Running totals of a walk, before hard links are resolved.
+#[derive(Debug, Default)]
+struct Tally {
+    allocated: u64,
+    apparent: u64,
+    files: u64,
+    linked: HashMap<Inode, LinkedFile>,
+}
+
+impl Tally {
+    fn from_metadata(metadata: &Metadata) -> Self {
+        let files = if metadata.is_dir() { 0 } else { 1 };
+        match linked_file(metadata) {
+            Some((inode, file)) => Tally {
+                files,
+                linked: HashMap::from([(inode, file)]),
+                ..Tally::default()
+            },
+            None => Tally {
+                allocated: allocated_bytes(metadata),
+                apparent: metadata.len(),
+                files,
+                linked: HashMap::new(),
+            },
+        }
+    }
+
+    fn merge(self, other: Tally) -> Tally {
+        let (mut into, from) = if self.linked.len() >= other.linked.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        into.allocated = into.allocated.saturating_add(from.allocated);
+        into.apparent = into.apparent.saturating_add(from.apparent);
+        into.files = into.files.saturating_add(from.files);
+        for (inode, file) in from.linked {
+            into.linked
+                .entry(inode)
+                .and_modify(|known| known.links += file.links)
+                .or_insert(file);
+        }
+        into
+    }
+
+    fn finish(self) -> DirSize {
+        let mut size = DirSize {
+            allocated: self.allocated,
+            apparent: self.apparent,
+            files: self.files,
+            exclusive: self.allocated,
+            shared: Vec::new(),
+        };
+        for (inode, file) in self.linked {
+            size.allocated = size.allocated.saturating_add(file.allocated);
+            size.apparent = size.apparent.saturating_add(file.apparent);
+            if file.is_complete() {
+                size.exclusive = size.exclusive.saturating_add(file.allocated);
+            } else {
+                size.shared.push((inode, file));
+            }
+        }
+        size
+    }
+}
+
+/// Allocated bytes removing the entry frees, none while other hard links remain.
+pub fn freed_bytes(metadata: &Metadata) -> u64 {
+    if linked_file(metadata).is_some() {
+        0
+    } else {
+        allocated_bytes(metadata)
+    }
+}
+
+#[cfg(unix)]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks().saturating_mul(512)
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+fn linked_file(metadata: &Metadata) -> Option<(Inode, LinkedFile)> {
+    use std::os::unix::fs::MetadataExt;
+    if metadata.is_dir() || metadata.nlink() < 2 {
+        return None;
+    }
+    Some((
+        (metadata.dev(), metadata.ino()),
+        LinkedFile {
+            allocated: allocated_bytes(metadata),
+            apparent: metadata.len(),
+            nlink: metadata.nlink(),
+            links: 1,
+        },
+    ))
+}
+
+#[cfg(not(unix))]
+fn linked_file(_metadata: &Metadata) -> Option<(Inode, LinkedFile)> {
+    None
+}
+
+fn with_path(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+fn tally(path: &Path, metadata: Metadata, control: &ScanControl) -> io::Result<Tally> {
+    if !metadata.is_dir() {
+        return Ok(Tally::from_metadata(&metadata));
+    }
+    if !control.proceed() {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "scan cancelled"));
+    }
+    let entries = fs::read_dir(path)
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .map_err(|e| with_path(path, e))?;
+    entries
+        .par_iter()
+        .map(|entry| {
+            let entry_path = entry.path();
+            let metadata = entry.metadata().map_err(|e| with_path(&entry_path, e))?;
+            tally(&entry_path, metadata, control)
+        })
+        .try_reduce(Tally::default, |a, b| Ok(a.merge(b)))
+        .map(|children| children.merge(Tally::from_metadata(&metadata)))
+}
+
+/// Computes the size of the tree rooted at `path`, visiting sub-directories in parallel.
+///
+/// Symbolic links are counted as links and never followed. The first error encountered
+/// anywhere in the tree is returned, annotated with the path that caused it.
+pub fn dir_size(path: &Path) -> io::Result<DirSize> {
+    paths_size(&[path.to_path_buf()], &ScanControl::default())
+}
+
+/// Like [`dir_size`], for a unit made of several trees. Files linked from more than
+/// one of them are counted once.
+///
+/// Sizing waits while `control` is paused, and gives up with an `Interrupted` error
+/// once it's cancelled.
+pub fn paths_size(paths: &[PathBuf], control: &ScanControl) -> io::Result<DirSize> {
+    paths
+        .par_iter()
+        .map(|path| {
+            let metadata = fs::symlink_metadata(path).map_err(|e| with_path(path, e))?;
+            tally(path, metadata, control)
+        })
+        .try_reduce(Tally::default, |a, b| Ok(a.merge(b)))
+        .map(Tally::finish)
+}
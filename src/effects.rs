@@ -1,116 +1,489 @@
+use crate::activity::last_active;
+use crate::breakdown::breakdown;
+use crate::deleter::DeleteQueue;
 use crate::dir_entry_item::DirEntryItem;
-use crate::event::{DirDelete, DirSearch, Event};
-use rayon::prelude::*;
+use crate::dir_size::paths_size;
+use crate::event::{DirSearch, Event};
+use crate::exclude::{Excludes, IGNORE_FILE};
+use crate::index::IndexEntry;
+use crate::package_manager::PackageManager;
+use crate::project::Project;
+use crate::quarantine::QUARANTINE_DIR;
+use crate::scan_control::ScanControl;
+use crate::targets::{Targets, NODE_ECOSYSTEM};
+#[cfg(target_os = "linux")]
+use crate::watch::Watcher;
+use crate::workspace::Workspace;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
     path::{Path, PathBuf},
-    process::Command,
-    sync::mpsc::Sender,
+    sync::{mpsc::Sender, Arc, OnceLock},
     thread::{self},
 };
 use walkdir::WalkDir;
 
-pub fn delete_items(items: Vec<DirEntryItem>, sender: &Sender<Event>) {
-    items.par_iter().for_each_with(sender.clone(), |s, item| {
-        s.send(Event::Delete(DirDelete::Deleting(item.entry.path().into())))
-            .expect("Unable to send data through the channel.");
-        let result: Result<(), std::io::Error> = std::fs::remove_dir_all(item.entry.path());
-        match result {
-            Ok(_) => {
-                s.send(Event::Delete(DirDelete::Deleted(item.entry.path().into())))
-                    .expect("Unable to send data through the channel.");
-            }
-            Err(e) => {
-                s.send(Event::Delete(DirDelete::Failed(
-                    item.entry.path().into(),
-                    e.to_string(),
-                )))
-                .expect("Unable to send data through the channel.");
-            }
+/// How deletions go.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeleteOptions {
+    /// Report items deleted without touching them.
+    pub dry_run: bool,
+    /// Move items to the trash instead of deleting them for good.
+    pub trash: bool,
+    /// Rename items into quarantine, they are purged in the background.
+    pub quarantine: bool,
+}
+
+/// Queues `items` for deletion by the workers of `queue`, which report the progress of
+/// each. A dry run reports them deleted without touching them.
+pub fn delete_items(
+    items: Vec<DirEntryItem>,
+    sender: &Sender<Event>,
+    options: DeleteOptions,
+    queue: &Arc<DeleteQueue>,
+) {
+    queue.push(items, options, sender);
+}
+
+/// Sizing jobs run on this pool, so a huge directory never stalls the walk, and sizing
+/// never takes over every core.
+fn sizing_pool() -> &'static ThreadPool {
+    static SIZING_POOL: OnceLock<ThreadPool> = OnceLock::new();
+    SIZING_POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .thread_name(|i| format!("sizing-{}", i))
+            .build()
+            .expect("Unable to create the sizing thread pool.")
+    })
+}
+
+/// Breaks down `item` on the sizing pool, as part of the scan `control` stands for.
+pub fn break_down_item(item: &DirEntryItem, sender: &Sender<Event>, control: &Arc<ScanControl>) {
+    let path = item.entry.path().to_path_buf();
+    let paths = item.paths.clone();
+    let sender = sender.clone();
+    let control = control.clone();
+    sizing_pool().spawn(move || {
+        if !control.proceed() {
+            return;
         }
+        let result = breakdown(&path, &paths, &control).map_err(|e| e.to_string());
+        control.send(&sender, DirSearch::Breakdown(path, result));
     });
 }
 
-#[cfg(target_os = "linux")]
-fn get_directory_size(path: &str) -> std::io::Result<u64> {
-    use std::process::Command;
-
-    let output = Command::new("du")
-        .arg("-s")
-        .arg("--block-size=1")
-        .arg(path)
-        .output()?;
-
-    let output_str = String::from_utf8(output.stdout)?;
-    let size_str = output_str.split_whitespace().next().ok_or_else(|| {
-        std::io::Error::new(std::io::ErrorKind::Other, "Failed to parse du output")
-    })?;
-    let size = size_str.parse::<u64>()?;
-    Ok(size)
+/// Where to look, and what for.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub roots: Vec<PathBuf>,
+    pub targets: Targets,
+    /// Exclusions from the command line and the config. `.dnmignore` files found
+    /// during the walk add to these for the directories below them.
+    pub excludes: Excludes,
+    /// How deep below each root to look.
+    pub max_depth: Option<usize>,
+    /// Don't cross into other filesystems than the root's.
+    pub one_file_system: bool,
+    /// Roots whose candidates are restored from the index instead of walked.
+    pub cached: HashMap<PathBuf, Vec<IndexEntry>>,
+    /// Keep watching the roots for changes once the scan is done.
+    pub watch: bool,
 }
 
-#[cfg(target_os = "macos")]
-fn get_directory_size(path: &str) -> Result<u64, Box<dyn std::error::Error>> {
-    // note this will only work on macos.
-    let output = Command::new("du")
-        .arg("-sk")
-        .arg(path)
-        .output()?;
-
-    let output_str = String::from_utf8(output.stdout)?;
-    let size_str = output_str.split_whitespace().next().ok_or("No output from du")?;
-    let size_kb = size_str.parse::<u64>()?;
-    let size_bytes = size_kb * 1024; // Convert from kilobytes to bytes
-    Ok(size_bytes)
+#[cfg(unix)]
+fn device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
 }
 
-pub fn walk_node_modules(sender: Sender<Event>) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        sender
-            .send(Event::Search(DirSearch::Started))
-            .expect("Unable to send data through the channel.");
-        let path = Path::new("."); // Start from the current directory.
-        let mut current: Option<PathBuf> = None;
-        let mut counter: u64 = 0;
-        let mut found: u64 = 0;
-        for entry in WalkDir::new(path)
+#[cfg(not(unix))]
+fn device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+fn report_activity(
+    sender: &Sender<Event>,
+    control: &ScanControl,
+    path: PathBuf,
+    paths: &[PathBuf],
+    project_root: Option<PathBuf>,
+) {
+    if let Some(project_root) = project_root {
+        let active = last_active(&project_root, paths, &mut |path, message| {
+            control.send(sender, DirSearch::Error(path, message))
+        });
+        control.send(sender, DirSearch::Activity(path, active));
+    }
+}
+
+/// Whether the walk of `root` leaves out `path`, a directory below it: it or a directory
+/// on the way is excluded, by `excludes` or by the ignore file of a directory above it.
+///
+/// `ignore_files` keeps the ignore files read, by directory, for the next calls.
+fn is_left_out(
+    root: &Path,
+    path: &Path,
+    excludes: &Excludes,
+    ignore_files: &mut HashMap<PathBuf, Option<Excludes>>,
+) -> bool {
+    let mut dirs: Vec<&Path> = path
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .collect();
+    dirs.reverse();
+    let mut in_effect: Vec<&Path> = Vec::new();
+    for dir in dirs {
+        if excludes.is_excluded(dir)
+            || in_effect.iter().any(|holder| {
+                ignore_files[*holder]
+                    .as_ref()
+                    .is_some_and(|excludes| excludes.is_excluded(dir))
+            })
+        {
+            return true;
+        }
+        // The walk reports ignore files it can't read, they exclude nothing.
+        let read = ignore_files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| Excludes::from_ignore_file(dir).ok().flatten());
+        if read.is_some() {
+            in_effect.push(dir);
+        }
+    }
+    false
+}
+
+/// State shared by the walks of one scan.
+pub(crate) struct Walk<'a> {
+    pub options: &'a ScanOptions,
+    pub sender: &'a Sender<Event>,
+    pub control: &'a Arc<ScanControl>,
+    pub sizing_pool: &'a ThreadPool,
+    /// Entries visited so far.
+    pub counter: u64,
+    /// Candidates found so far.
+    pub found: u64,
+    /// Workspaces declared in the directories looked at so far.
+    pub workspaces: HashMap<PathBuf, Option<Arc<Workspace>>>,
+    /// Candidates restored from the index and not found again by the walk yet, as the
+    /// index had them.
+    pub restored: HashMap<PathBuf, DirEntryItem>,
+    /// Ignore files read so far.
+    pub ignore_files: Vec<PathBuf>,
+}
+
+impl Walk<'_> {
+    /// The workspace the project at `project_root` is a package of, if any.
+    pub(crate) fn workspace_of(&mut self, project_root: &Path) -> Option<Arc<Workspace>> {
+        project_root.ancestors().find_map(|dir| {
+            self.workspaces
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| Workspace::declared_in(dir).map(Arc::new))
+                .clone()
+                .filter(|workspace| workspace.contains(project_root))
+        })
+    }
+
+    /// Reports `event`, unless the scan was cancelled.
+    pub(crate) fn send(&self, event: DirSearch) {
+        self.control.send(self.sender, event);
+    }
+
+    /// Sizes the candidate at `path` and works out its project's activity, when it has
+    /// one, off the walk.
+    pub(crate) fn spawn_sizing(
+        &self,
+        path: PathBuf,
+        paths: Vec<PathBuf>,
+        project_root: Option<PathBuf>,
+    ) {
+        let sender = self.sender.clone();
+        let control = self.control.clone();
+        self.sizing_pool.spawn(move || {
+            if !control.proceed() {
+                return;
+            }
+            let size = paths_size(&paths, &control).map_err(|e| e.to_string());
+            control.send(&sender, DirSearch::Sized(path.clone(), size));
+            report_activity(&sender, &control, path, &paths, project_root);
+        });
+    }
+
+    /// Works out the activity of the project of the candidate at `path`, off the walk.
+    pub(crate) fn spawn_activity(
+        &self,
+        path: PathBuf,
+        paths: Vec<PathBuf>,
+        project_root: Option<PathBuf>,
+    ) {
+        let sender = self.sender.clone();
+        let control = self.control.clone();
+        self.sizing_pool.spawn(move || {
+            if !control.proceed() {
+                return;
+            }
+            report_activity(&sender, &control, path, &paths, project_root);
+        });
+    }
+
+    /// Walks the tree at `start`, which is `root` itself or a directory below it, and
+    /// reports the candidates in it.
+    ///
+    /// `visit` sees every directory that is kept, along with the item when it is a
+    /// candidate. The walk doesn't descend into candidates.
+    pub(crate) fn tree(
+        &mut self,
+        root: &Path,
+        start: &Path,
+        visit: &mut dyn FnMut(&Path, Option<&DirEntryItem>),
+    ) {
+        let options = self.options;
+        let (sender, control) = (self.sender, self.control);
+        let offset = start
+            .strip_prefix(root)
+            .map_or(0, |rest| rest.components().count());
+        let max_depth = match options.max_depth {
+            Some(max_depth) if offset > max_depth => return,
+            Some(max_depth) => max_depth - offset,
+            None => usize::MAX,
+        };
+        // Ignore files of the directories between the root and `start`, in effect for
+        // the whole walk.
+        let inherited: Vec<Excludes> = start
+            .ancestors()
+            .skip(1)
+            .take(offset)
+            .filter_map(|dir| Excludes::from_ignore_file(dir).ok().flatten())
+            .collect();
+        // Ignore files in effect, with the depth of the directory holding them.
+        // The walk is depth first, so leaving a directory pops its ignore file.
+        let mut ignore_files: Vec<(usize, Excludes)> = Vec::new();
+        let mut read_ignore_files: Vec<PathBuf> = Vec::new();
+        let root_device = fs::metadata(root)
+            .ok()
+            .and_then(|metadata| device(&metadata))
+            .filter(|_| options.one_file_system);
+        let mut walker = WalkDir::new(start)
             .follow_links(false) // Do not follow symbolic links.
+            .max_depth(max_depth)
             .into_iter()
-            .filter_map(Result::ok)
-        {
-            if counter % 100 == 0 {
-                sender
-                    .send(Event::Search(DirSearch::Progress(counter)))
-                    .expect("Unable to send data through the channel.");
+            .filter_entry(|entry| {
+                if let Some(root_device) = root_device {
+                    if entry.depth() + offset > 0
+                        && entry.file_type().is_dir()
+                        && entry
+                            .metadata()
+                            .ok()
+                            .and_then(|metadata| device(&metadata))
+                            .is_some_and(|device| device != root_device)
+                    {
+                        control.send(sender, DirSearch::MountSkipped(entry.path().to_path_buf()));
+                        return false;
+                    }
+                }
+                while ignore_files
+                    .last()
+                    .is_some_and(|(depth, _)| *depth >= entry.depth())
+                {
+                    ignore_files.pop();
+                }
+                // Our own quarantined directories are on their way out.
+                if entry.file_type().is_dir() && entry.file_name() == QUARANTINE_DIR {
+                    control.send(sender, DirSearch::Quarantine(entry.path().to_path_buf()));
+                    return false;
+                }
+                if options.excludes.is_excluded(entry.path())
+                    || inherited
+                        .iter()
+                        .chain(ignore_files.iter().map(|(_, excludes)| excludes))
+                        .any(|excludes| excludes.is_excluded(entry.path()))
+                {
+                    return false;
+                }
+                if entry.file_type().is_dir() {
+                    match Excludes::from_ignore_file(entry.path()) {
+                        Ok(Some(excludes)) => {
+                            ignore_files.push((entry.depth(), excludes));
+                            read_ignore_files.push(entry.path().join(IGNORE_FILE));
+                        }
+                        Ok(None) => {}
+                        Err(e) => control.send(
+                            sender,
+                            DirSearch::Error(entry.path().join(IGNORE_FILE), e.to_string()),
+                        ),
+                    }
+                }
+                true
+            });
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // The path is reported on its own, keep the message to the cause.
+                    let path = e.path().unwrap_or(start).to_path_buf();
+                    let message = e
+                        .io_error()
+                        .map_or_else(|| e.to_string(), |io_error| io_error.to_string());
+                    self.send(DirSearch::Error(path, message));
+                    continue;
+                }
+            };
+            if !self.control.proceed() {
+                return;
             }
-            counter += 1;
-            if entry.file_type().is_dir()
-                && entry
-                    .path()
-                    .file_name()
-                    .map_or(false, |name| name == "node_modules")
-            {
-                if let Some(ref previous) = current {
-                    if entry.path().starts_with(previous) {
-                        // Skip this entry because it's under a `node_modules` directory we've already processed.
-                        continue;
+            if self.counter.is_multiple_of(100) {
+                self.send(DirSearch::Progress(self.counter));
+            }
+            self.counter += 1;
+            let Some(target) = options.targets.matching(&entry) else {
+                if entry.file_type().is_dir() {
+                    visit(entry.path(), None);
+                }
+                continue;
+            };
+            // Don't descend any further, the size pass is the only thing that reads inside.
+            walker.skip_current_dir();
+            let paths = target.removable_paths(entry.path());
+            if paths.is_empty() {
+                // A unit whose parts are all gone already.
+                continue;
+            }
+            let path = entry.path().to_path_buf();
+            let mut item =
+                DirEntryItem::from_entry(root.to_path_buf(), entry, target.ecosystem.clone());
+            item.paths = paths.clone();
+            item.modified = item
+                .entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok());
+            item.project = Project::find(&path);
+            let project_root = item.project_root().to_path_buf();
+            if target.ecosystem == NODE_ECOSYSTEM {
+                item.package_manager = PackageManager::detect(&project_root, &path);
+                item.workspace = self.workspace_of(&project_root);
+            }
+            visit(&path, Some(&item));
+            let activity_root = item.activity_root().map(Path::to_path_buf);
+            // Restored from the index, already listed. It's only measured now, going by
+            // what the candidate is made of today rather than what the index remembers.
+            if let Some(restored) = self.restored.remove(&path) {
+                let changed = restored.paths != item.paths;
+                if changed
+                    || restored.project != item.project
+                    || restored.package_manager != item.package_manager
+                {
+                    self.send(DirSearch::Changed(Box::new(item)));
+                }
+                if changed || restored.is_sizing() {
+                    self.spawn_sizing(path, paths, activity_root);
+                } else {
+                    self.spawn_activity(path, paths, activity_root);
+                }
+                continue;
+            }
+            // Send each valid directory entry through the channel right away,
+            // its size follows once a sizing worker gets to it.
+            self.found += 1;
+            self.send(DirSearch::Found(Box::new(item)));
+            self.spawn_sizing(path, paths, activity_root);
+        }
+        drop(walker);
+        self.ignore_files.extend(read_ignore_files);
+    }
+}
+
+/// Scans the roots on a thread of its own, reporting through `sender`.
+///
+/// `control` pauses and cancels the scan, and the sizing and watching that follow it.
+pub fn walk_node_modules(
+    sender: Sender<Event>,
+    mut options: ScanOptions,
+    control: Arc<ScanControl>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        control.send(&sender, DirSearch::Started);
+        #[cfg(target_os = "linux")]
+        let mut watcher = if options.watch {
+            match Watcher::new() {
+                Ok(watcher) => Some(watcher),
+                Err(e) => {
+                    // Typically `max_user_instances` reached, the list won't follow changes.
+                    for root in &options.roots {
+                        control.send(
+                            &sender,
+                            DirSearch::Error(root.clone(), format!("can't watch: {}", e)),
+                        );
                     }
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let mut cached = std::mem::take(&mut options.cached);
+        let mut walk = Walk {
+            options: &options,
+            sender: &sender,
+            control: &control,
+            sizing_pool: sizing_pool(),
+            counter: 0,
+            found: 0,
+            workspaces: HashMap::new(),
+            restored: HashMap::new(),
+            ignore_files: Vec::new(),
+        };
+        // Restore what the index knows right away, the walk measures it again once it
+        // finds it. Candidates that disappeared are dropped.
+        let mut ignore_files = HashMap::new();
+        for root in &options.roots {
+            let Some(entries) = cached.remove(root) else {
+                continue;
+            };
+            for mut item in entries
+                .into_iter()
+                .filter(|entry| {
+                    !is_left_out(root, &entry.path, &options.excludes, &mut ignore_files)
+                })
+                .filter_map(|entry| entry.into_item(root.clone()))
+            {
+                walk.found += 1;
+                let project_root = item.project_root().to_path_buf();
+                if item.ecosystem == NODE_ECOSYSTEM {
+                    item.workspace = walk.workspace_of(&project_root);
                 }
-                // Update the current path and calculate size.
-                current = Some(entry.path().to_path_buf());
-                let size = entry
-                    .path()
-                    .to_str()
-                    .map(|str| get_directory_size(str).unwrap_or(0))
-                    .unwrap_or(0);
-                // Send each valid directory entry through the channel.
-                found += 1;
-                sender
-                    .send(Event::Search(DirSearch::Found(entry, size)))
-                    .expect("Unable to send data through the channel.");
+                walk.restored
+                    .insert(item.entry.path().to_path_buf(), item.clone());
+                walk.send(DirSearch::Found(Box::new(item)));
             }
         }
-        sender
-            .send(Event::Search(DirSearch::Finished(counter, found)))
-            .expect("Unable to send finish event.");
+        // Then walk every root, to find what's new since the index was written.
+        for root in &options.roots {
+            #[cfg(target_os = "linux")]
+            let mut visit = |path: &Path, item: Option<&DirEntryItem>| {
+                if let Some(watcher) = watcher.as_mut() {
+                    watcher.add(root, path, item);
+                }
+            };
+            #[cfg(not(target_os = "linux"))]
+            let mut visit = |_: &Path, _: Option<&DirEntryItem>| {};
+            walk.tree(root, root, &mut visit);
+        }
+        if control.is_cancelled() {
+            return;
+        }
+        // Restored candidates the walk didn't find again are gone, or no longer wanted.
+        for path in std::mem::take(&mut walk.restored).into_keys() {
+            walk.send(DirSearch::Removed(path));
+        }
+        for path in std::mem::take(&mut walk.ignore_files) {
+            walk.send(DirSearch::IgnoreFile(path));
+        }
+        walk.send(DirSearch::Finished(walk.counter, walk.found));
+        #[cfg(target_os = "linux")]
+        if let Some(watcher) = watcher {
+            watcher.run(&mut walk);
+        }
     })
 }
@@ -1,20 +1,48 @@
+use crate::breakdown::Breakdown;
+use crate::dir_entry_item::DirEntryItem;
+use crate::dir_size::DirSize;
+use crate::failure::DeleteFailure;
 use crossterm::event::{KeyEvent, MouseEvent};
 use std::path::PathBuf;
-use walkdir::DirEntry;
+use std::time::SystemTime;
 
 #[derive(Clone, Debug)]
 pub enum DirSearch {
     Started,
     Finished(u64,u64),
-    Found(DirEntry, u64),
-    Progress(u64)
+    Found(Box<DirEntryItem>),
+    Sized(PathBuf, Result<DirSize, String>),
+    Activity(PathBuf, Option<SystemTime>),
+    Progress(u64),
+    /// A mount point left out by `--one-file-system`.
+    MountSkipped(PathBuf),
+    /// A candidate that disappeared while watching, or that the walk following a restore
+    /// from the index didn't find again.
+    Removed(PathBuf),
+    /// A candidate restored from the index that the walk found made differently, with
+    /// what it's made of now. Its size follows when its parts changed.
+    Changed(Box<DirEntryItem>),
+    /// An ignore file the scan went by, what it found depends on it.
+    IgnoreFile(PathBuf),
+    /// A quarantine directory the scan came across, maybe holding trees nobody purges.
+    Quarantine(PathBuf),
+    /// A path the scan couldn't read, and why.
+    Error(PathBuf, String),
+    /// What a candidate is made of, asked for by expanding it.
+    Breakdown(PathBuf, Result<Breakdown, String>),
 }
 
 #[derive(Clone, Debug)]
 pub enum DirDelete {
     Deleting(PathBuf),
+    /// Bytes freed and files removed so far.
+    Progress(PathBuf, u64, u64),
+    /// Deleted for good rather than as asked, and why.
+    Permanent(PathBuf, String),
     Deleted(PathBuf),
-    Failed(PathBuf, String),
+    /// Stopped by cancelling the deletions, maybe after removing part of the item.
+    Cancelled(PathBuf),
+    Failed(PathBuf, DeleteFailure),
 }
 
 /// Terminal events.
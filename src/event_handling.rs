@@ -1,6 +1,5 @@
 use crate::app::App;
-use crate::dir_entry_item::DirEntryItem;
-use crate::effects::delete_items;
+use crate::effects::{break_down_item, delete_items, walk_node_modules};
 use crate::event::Event;
 use crate::tui::Tui;
 use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyModifiers};
@@ -39,7 +38,31 @@ pub fn handle_event<B: Backend>(event: Event, app: &mut App, tui: &Tui<B, Event>
     match event {
         Event::Tick => app.tick(),
         Event::Key(key_event) => {
-            if app.is_in_search_mode {
+            if let Some(confirmation) = app.confirmation.as_ref() {
+                let needs_typing = confirmation.needs_typing;
+                match key_event.code {
+                    KeyCode::Char('c') | KeyCode::Char('C')
+                        if key_event.modifiers == KeyModifiers::CONTROL =>
+                    {
+                        app.quit();
+                    }
+                    KeyCode::Esc => app.cancel_delete(),
+                    KeyCode::Enter => {
+                        if let Some(items) = app.confirm_delete() {
+                            delete_items(items, &tui.sender, app.delete_options, &app.delete_queue);
+                        }
+                    }
+                    KeyCode::Backspace => app.erase_confirmation(),
+                    KeyCode::Char(c) if needs_typing => app.type_confirmation(c),
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        if let Some(items) = app.confirm_delete() {
+                            delete_items(items, &tui.sender, app.delete_options, &app.delete_queue);
+                        }
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') => app.cancel_delete(),
+                    _ => {}
+                }
+            } else if app.is_in_search_mode {
                 match key_event.code {
                     KeyCode::Esc => {
                         app.end_search_entry();
@@ -65,16 +88,23 @@ pub fn handle_event<B: Backend>(event: Event, app: &mut App, tui: &Tui<B, Event>
                         app.quit();
                     }
                     // Exit application on `Ctrl-C`
-                    KeyCode::Char('c') | KeyCode::Char('C') => {
-                        if key_event.modifiers == KeyModifiers::CONTROL {
-                            app.quit();
-                        }
+                    KeyCode::Char('c') | KeyCode::Char('C')
+                        if key_event.modifiers == KeyModifiers::CONTROL =>
+                    {
+                        app.quit();
                     }
                     KeyCode::Char(' ') => {
                         app.toggle_selected_item();
                     }
                     KeyCode::Right => app.set_on_and_next(),
                     KeyCode::Left => app.set_off_and_next(),
+                    // The bottom panel takes the arrows while it's open.
+                    KeyCode::Down if app.show_report => app.scroll_report(1),
+                    KeyCode::Up if app.show_report => app.scroll_report(-1),
+                    KeyCode::Down if app.show_failures => app.scroll_failures(1),
+                    KeyCode::Up if app.show_failures => app.scroll_failures(-1),
+                    KeyCode::Down if app.show_errors => app.scroll_errors(1),
+                    KeyCode::Up if app.show_errors => app.scroll_errors(-1),
                     KeyCode::Down => app.next(),
                     KeyCode::Up => app.previous(),
                     KeyCode::Tab => {
@@ -86,13 +116,67 @@ pub fn handle_event<B: Backend>(event: Event, app: &mut App, tui: &Tui<B, Event>
                     KeyCode::Char('/') => {
                         app.start_search_entry();
                     }
-                    KeyCode::Enter => {
-                        let items: Vec<DirEntryItem> =
-                            app.list.items_to_delete().cloned().collect();
+                    KeyCode::Char('p') | KeyCode::Char('P') => {
+                        app.toggle_project_names();
+                    }
+                    KeyCode::Char('s') | KeyCode::Char('S') => {
+                        app.cycle_sort();
+                    }
+                    KeyCode::Char('o') | KeyCode::Char('O') => {
+                        app.cycle_age_filter();
+                    }
+                    KeyCode::Char('d') | KeyCode::Char('D') => {
+                        if let Some(item) = app.toggle_expanded() {
+                            break_down_item(&item, &tui.sender, &app.scan_control);
+                        }
+                    }
+                    KeyCode::Char('e') | KeyCode::Char('E') => {
+                        app.toggle_errors();
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') => {
+                        app.toggle_dry_run();
+                    }
+                    KeyCode::Char('v') | KeyCode::Char('V') => {
+                        app.toggle_report();
+                    }
+                    KeyCode::Char('u') | KeyCode::Char('U') => {
+                        app.toggle_delete_pause();
+                    }
+                    KeyCode::Char('c') | KeyCode::Char('C') => {
+                        app.cancel_deletions();
+                    }
+                    KeyCode::Char('b') | KeyCode::Char('B') => {
+                        app.restore_selected();
+                    }
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        app.toggle_failures();
+                    }
+                    KeyCode::Char('t') | KeyCode::Char('T') => {
+                        let items = app.retry_failed();
                         if !items.is_empty() {
-                            delete_items(items, &tui.sender);
+                            delete_items(items, &tui.sender, app.delete_options, &app.delete_queue);
                         }
                     }
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        app.change_delete_jobs(1);
+                    }
+                    KeyCode::Char('-') => {
+                        app.change_delete_jobs(-1);
+                    }
+                    KeyCode::Char('z') | KeyCode::Char('Z') => {
+                        app.toggle_scan_pause();
+                    }
+                    KeyCode::Char('x') | KeyCode::Char('X') => {
+                        app.cancel_scan();
+                    }
+                    KeyCode::Char('r') | KeyCode::Char('R') => {
+                        if let Some((options, control)) = app.rescan() {
+                            walk_node_modules(tui.sender.clone(), options, control);
+                        }
+                    }
+                    KeyCode::Enter => {
+                        app.request_delete();
+                    }
                     // Other handlers you could add here.
                     _ => {}
                 }
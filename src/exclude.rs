@@ -0,0 +1,105 @@
+use crate::app::AppResult;
+use crate::cli::expand_tilde;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory ignore file.
+pub const IGNORE_FILE: &str = ".dnmignore";
+
+/// A set of exclusion globs.
+///
+/// Patterns without a `/` match the name of any directory, like `.git` or `*.backup`.
+/// Patterns with a `/` match the whole path; they are relative to the ignore file's
+/// directory for ignore files, and absolute (after `~` expansion) otherwise.
+#[derive(Debug, Clone)]
+pub struct Excludes {
+    /// The patterns as given.
+    patterns: Vec<String>,
+    base: Option<PathBuf>,
+    names: GlobSet,
+    paths: GlobSet,
+}
+
+impl Excludes {
+    /// Builds the exclusions given on the command line or in the config.
+    pub fn new(patterns: &[String]) -> AppResult<Excludes> {
+        Self::build(patterns.iter().map(String::as_str), None)
+    }
+
+    /// Reads `dir/.dnmignore`, if there is one.
+    ///
+    /// Empty lines and lines starting with `#` are skipped. An ignore file that can't be
+    /// read or holds an invalid glob is an error.
+    pub fn from_ignore_file(dir: &Path) -> AppResult<Option<Excludes>> {
+        let text = match fs::read_to_string(dir.join(IGNORE_FILE)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            // The walk reports directories it can't read, not their ignore file.
+            Err(_) if fs::read_dir(dir).is_err() => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let patterns = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        Self::build(patterns, Some(dir.to_path_buf())).map(Some)
+    }
+
+    fn build<'a>(
+        patterns: impl Iterator<Item = &'a str>,
+        base: Option<PathBuf>,
+    ) -> AppResult<Excludes> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        let patterns: Vec<String> = patterns.map(str::to_string).collect();
+        for pattern in &patterns {
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.contains('/') {
+                let pattern = match base {
+                    Some(_) => pattern.trim_start_matches('/').to_string(),
+                    None => expand_tilde(Path::new(pattern)).display().to_string(),
+                };
+                paths.add(
+                    GlobBuilder::new(&pattern)
+                        .literal_separator(true)
+                        .build()
+                        .map_err(|e| format!("invalid pattern `{}`: {}", pattern, e))?,
+                );
+            } else {
+                names.add(
+                    GlobBuilder::new(pattern)
+                        .build()
+                        .map_err(|e| format!("invalid pattern `{}`: {}", pattern, e))?,
+                );
+            }
+        }
+        Ok(Excludes {
+            patterns,
+            base,
+            names: names.build()?,
+            paths: paths.build()?,
+        })
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Whether `path` should be neither listed nor descended into.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if path
+            .file_name()
+            .is_some_and(|name| self.names.is_match(name))
+        {
+            return true;
+        }
+        match &self.base {
+            Some(base) => path
+                .strip_prefix(base)
+                .is_ok_and(|relative| self.paths.is_match(relative)),
+            None => self.paths.is_match(path),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The usual reasons a deletion fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// `EACCES` or `EPERM`, typically files left by `sudo npm install`.
+    Permission,
+    /// `EBUSY` or `ETXTBSY`, something still uses the file.
+    Busy,
+    /// `EROFS`.
+    ReadOnly,
+    Other,
+}
+
+impl FailureKind {
+    fn of(error: &io::Error) -> FailureKind {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => FailureKind::Permission,
+            io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy => FailureKind::Busy,
+            io::ErrorKind::ReadOnlyFilesystem => FailureKind::ReadOnly,
+            _ => FailureKind::Other,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Permission => "permission denied",
+            FailureKind::Busy => "busy",
+            FailureKind::ReadOnly => "read-only filesystem",
+            FailureKind::Other => "failed",
+        }
+    }
+}
+
+/// Why deleting an item failed, down to the entry that couldn't be removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteFailure {
+    /// The entry inside the tree that couldn't be removed.
+    pub path: PathBuf,
+    pub kind: FailureKind,
+    /// The error as the system put it.
+    pub message: String,
+    /// What would let a retry succeed, when there is a usual fix.
+    pub suggestion: Option<String>,
+}
+
+impl DeleteFailure {
+    /// The failure to remove `path`, inside the tree at `tree`.
+    pub fn new(tree: &Path, path: &Path, error: &io::Error) -> DeleteFailure {
+        let kind = FailureKind::of(error);
+        let suggestion = match kind {
+            FailureKind::Permission => Some(permission_fix(tree, path)),
+            FailureKind::Busy => Some(
+                "stop what is using it, like a dev server, a file watcher or an editor, then retry"
+                    .to_string(),
+            ),
+            FailureKind::ReadOnly => {
+                Some("remount the filesystem read-write, then retry".to_string())
+            }
+            FailureKind::Other => None,
+        };
+        DeleteFailure {
+            path: path.to_path_buf(),
+            kind,
+            message: error.to_string(),
+            suggestion,
+        }
+    }
+}
+
+/// Removing an entry takes write access to the directory holding it. A directory owned
+/// by someone else, most often root, needs its ownership back, one of our own only its
+/// permissions.
+#[cfg(unix)]
+fn permission_fix(tree: &Path, path: &Path) -> String {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    // SAFETY: getuid has no preconditions and can't fail.
+    let uid = unsafe { libc::getuid() };
+    let owner = path
+        .parent()
+        .and_then(|dir| fs::symlink_metadata(dir).ok())
+        .map(|metadata| metadata.uid());
+    match owner {
+        Some(owner) if owner != uid => format!(
+            "{} is owned by {}, likely from a `sudo` install: run `sudo chown -R $(id -un) {}`, then retry",
+            path.parent().unwrap_or(path).display(),
+            if owner == 0 {
+                "root".to_string()
+            } else {
+                format!("user {}", owner)
+            },
+            tree.display()
+        ),
+        _ => format!("run `chmod -R u+w {}`, then retry", tree.display()),
+    }
+}
+
+#[cfg(not(unix))]
+fn permission_fix(tree: &Path, _path: &Path) -> String {
+    format!(
+        "make {} writable, or close what has it open, then retry",
+        tree.display()
+    )
+}
@@ -0,0 +1,188 @@
+use crate::actions::ActionState;
+use crate::app::AppResult;
+use crate::dir_entry_item::{dir_entry, DirEntryItem};
+use crate::dir_size::{Inode, LinkedFile};
+use crate::effects::ScanOptions;
+use crate::package_manager::PackageManager;
+use crate::project::Project;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Sizes as they were when the entry was last sized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedSize {
+    pub allocated: u64,
+    pub apparent: u64,
+    pub files: u64,
+    pub exclusive: u64,
+    /// Files also linked from outside the entry, to tally a selection's hard links.
+    pub shared: Vec<(Inode, LinkedFile)>,
+}
+
+/// What the index remembers about a candidate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub path: PathBuf,
+    pub paths: Vec<PathBuf>,
+    pub ecosystem: String,
+    pub package_manager: Option<PackageManager>,
+    pub project: Option<Project>,
+    pub last_active: Option<SystemTime>,
+    /// The candidate's mtime when it was sized.
+    pub modified: Option<SystemTime>,
+    /// `None` when the entry wasn't sized yet.
+    pub size: Option<IndexedSize>,
+}
+
+impl IndexEntry {
+    pub fn from_item(item: &DirEntryItem) -> IndexEntry {
+        IndexEntry {
+            path: item.entry.path().to_path_buf(),
+            paths: item.paths.clone(),
+            ecosystem: item.ecosystem.clone(),
+            package_manager: item.package_manager,
+            project: item.project.clone(),
+            last_active: item.last_active,
+            modified: item.modified,
+            size: matches!(item.size_state, ActionState::Done).then_some(IndexedSize {
+                allocated: item.size,
+                apparent: item.apparent_size,
+                files: item.files,
+                exclusive: item.exclusive_size,
+                shared: item.shared_files.to_vec(),
+            }),
+        }
+    }
+
+    /// Rebuilds the list item, or `None` if the candidate is gone.
+    ///
+    /// The size is only restored while the candidate's mtime is unchanged; otherwise the
+    /// item is left to be sized again. The remembered activity only stands until the
+    /// scan works it out again.
+    pub fn into_item(self, root: PathBuf) -> Option<DirEntryItem> {
+        let entry = dir_entry(&self.path)?;
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+        let mut item = DirEntryItem::from_entry(root, entry, self.ecosystem);
+        item.paths = self.paths;
+        item.package_manager = self.package_manager;
+        item.project = self.project;
+        item.last_active = self.last_active;
+        item.modified = modified;
+        if let Some(size) = self.size.filter(|_| modified == self.modified) {
+            item.size = size.allocated;
+            item.apparent_size = size.apparent;
+            item.files = size.files;
+            item.exclusive_size = size.exclusive;
+            item.shared_files = size.shared.into();
+            item.size_state = ActionState::Done;
+        }
+        Some(item)
+    }
+}
+
+/// What decides which candidates a scan of a root finds, besides the tree itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanKey {
+    pub profiles: Vec<String>,
+    pub max_depth: Option<usize>,
+    pub one_file_system: bool,
+    /// Exclusions from the command line and the config.
+    pub excludes: Vec<String>,
+}
+
+impl ScanKey {
+    pub fn of(options: &ScanOptions) -> ScanKey {
+        ScanKey {
+            profiles: options.targets.names(),
+            max_depth: options.max_depth,
+            one_file_system: options.one_file_system,
+            excludes: options.excludes.patterns().to_vec(),
+        }
+    }
+}
+
+/// The candidates found under one root, with what the scan looked for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRecord {
+    pub root: PathBuf,
+    pub key: ScanKey,
+    /// The ignore files the scan went by, and their mtime then.
+    pub ignore_files: Vec<(PathBuf, Option<SystemTime>)>,
+    pub entries: Vec<IndexEntry>,
+}
+
+/// Results of previous scans, kept in `$XDG_CACHE_HOME/delete-node-modules/index.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    scans: Vec<ScanRecord>,
+}
+
+impl Index {
+    pub fn path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("delete-node-modules").join("index.json"))
+    }
+
+    /// Loads the index. A missing or unreadable index is an empty one, it's only a cache.
+    pub fn load() -> Index {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the index, replacing the previous one atomically.
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::path().ok_or("no cache directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(self)?)?;
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /// The entries of a scan of `root` for exactly `key`, unless one of its ignore files
+    /// changed since.
+    pub fn entries(&self, root: &Path, key: &ScanKey) -> Option<&[IndexEntry]> {
+        self.scans
+            .iter()
+            .find(|scan| scan.root == root && scan.key == *key)
+            .filter(|scan| {
+                scan.ignore_files
+                    .iter()
+                    .all(|(path, modified)| mtime(path) == *modified)
+            })
+            .map(|scan| scan.entries.as_slice())
+    }
+
+    /// Replaces the record of `root`, found going by `ignore_files`.
+    pub fn update(
+        &mut self,
+        root: PathBuf,
+        key: ScanKey,
+        ignore_files: Vec<PathBuf>,
+        entries: Vec<IndexEntry>,
+    ) {
+        self.scans.retain(|scan| scan.root != root);
+        let ignore_files = ignore_files
+            .into_iter()
+            .map(|path| {
+                let modified = mtime(&path);
+                (path, modified)
+            })
+            .collect();
+        self.scans.push(ScanRecord {
+            root,
+            key,
+            ignore_files,
+            entries,
+        });
+    }
+}
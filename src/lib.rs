@@ -1,6 +1,12 @@
 /// Application.
 pub mod app;
 
+/// Command-line arguments.
+pub mod cli;
+
+/// Config file.
+pub mod config;
+
 /// Terminal events handler.
 pub mod event;
 
@@ -14,8 +20,40 @@ pub mod list;
 
 pub mod dir_entry_item;
 
+pub mod dir_size;
+
+pub mod breakdown;
+
 pub mod effects;
 
+pub mod deleter;
+
 pub mod event_handling;
 
-pub mod actions;
\ No newline at end of file
+pub mod actions;
+
+pub mod targets;
+
+pub mod project;
+
+pub mod activity;
+
+pub mod exclude;
+
+pub mod package_manager;
+
+pub mod workspace;
+
+pub mod index;
+
+pub mod scan_control;
+
+#[cfg(target_os = "linux")]
+pub mod watch;
+
+#[cfg(unix)]
+pub mod trash;
+
+pub mod quarantine;
+
+pub mod failure;
@@ -1,4 +1,5 @@
 use crate::{actions::ActionState, app::GroupSelection};
+use std::cmp::Ordering;
 use tui::widgets::*;
 
 pub trait Toggle {
@@ -22,6 +23,10 @@ pub trait GroupSelectable {
 }
 
 pub trait Deletable {
+    // Check whether the item's state lets it be deleted, selected or not.
+    fn is_deletable(&self) -> bool;
+
+    // Check whether the item is selected and can be deleted.
     fn can_delete(&self) -> bool;
 }
 
@@ -118,6 +123,47 @@ impl<T: Toggle> Default for StatefulList<T> {
 
 impl<T: Toggle> StatefulList<T> {
 
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Removes the items matching `predicate`, keeping the selection on the same item.
+    pub fn remove_where<P>(&mut self, predicate: P) -> bool
+    where
+        P: Fn(&T) -> bool,
+    {
+        // new_index[old] is where the item at `old` ends up, if it stays.
+        let mut kept = 0;
+        let new_index: Vec<Option<usize>> = self
+            .items
+            .iter()
+            .map(|item| {
+                (!predicate(item)).then(|| {
+                    kept += 1;
+                    kept - 1
+                })
+            })
+            .collect();
+        if kept == self.items.len() {
+            return false;
+        }
+        self.items.retain(|item| !predicate(item));
+        if let Some(filtered) = self.filtered_indices.as_mut() {
+            *filtered = filtered
+                .iter()
+                .filter_map(|&index| new_index[index])
+                .collect();
+        }
+        let selected = self.state.selected().and_then(|old| new_index[old]);
+        self.state.select(selected);
+        true
+    }
+
+    /// All items, filtered out or not.
+    pub fn items(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
     pub fn has_visible_items(&self) -> bool {
         self.visible_items().next().is_some()
     }
@@ -197,6 +243,44 @@ impl<T: Toggle> StatefulList<T> {
         self.state.select(next_index);
     }
 
+    /// The position of the selected item among the visible items.
+    pub fn selected_position(&self) -> Option<usize> {
+        let selected = self.state.selected()?;
+        match &self.filtered_indices {
+            Some(filtered) => filtered.iter().position(|&index| index == selected),
+            None => Some(selected),
+        }
+    }
+
+    /// Sorts the items, keeping the same item selected.
+    ///
+    /// The filter is kept as well, its indices are remapped to the new order.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        order.sort_by(|&a, &b| compare(&self.items[a], &self.items[b]));
+        // new_index[old] is where the item at `old` ends up.
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+        let mut items: Vec<Option<T>> = std::mem::take(&mut self.items)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.items = order.iter().filter_map(|&old| items[old].take()).collect();
+        if let Some(filtered) = self.filtered_indices.as_mut() {
+            for index in filtered.iter_mut() {
+                *index = new_index[*index];
+            }
+            filtered.sort_unstable();
+        }
+        let selected = self.state.selected().map(|old| new_index[old]);
+        self.state.select(selected);
+    }
+
     pub fn unselect(&mut self) {
         self.state.select(None);
     }
@@ -216,6 +300,18 @@ impl<T: Toggle> StatefulList<T> {
         }
     }
 
+    /// Like [`StatefulList::mutate_where`], for every matching item.
+    pub fn mutate_each<F, P>(&mut self, predicate: P, mutator: F)
+    where
+        F: FnMut(&mut T),
+        P: Fn(&T) -> bool,
+    {
+        self.items
+            .iter_mut()
+            .filter(|item| predicate(item))
+            .for_each(mutator);
+    }
+
     pub fn mutate_where<F, P>(&mut self, predicate: P, mutator: F) -> bool
     where
         F: FnOnce(&mut T),
@@ -234,10 +330,130 @@ impl<T: Toggle + Deletable> StatefulList<T> {
     pub fn items_to_delete<'b>(&'b self) -> Box<dyn Iterator<Item = &'b T> + 'b> {
         if let Some(group_selection) = self.group_selection.as_ref() {
             match group_selection {
-                GroupSelection::All => return self.visible_items(),
+                GroupSelection::All => {
+                    return Box::new(self.visible_items().filter(|item| item.is_deletable()))
+                }
                 GroupSelection::None => return Box::new(std::iter::empty()),
             }
         }
         Box::new(self.visible_items().filter(|item| item.can_delete()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(u32);
+
+    impl Toggle for Item {
+        fn toggle(&mut self) {}
+
+        fn set_is_on(&mut self, _is_on: bool) {}
+
+        fn is_on(&self) -> bool {
+            false
+        }
+    }
+
+    // Odd items are already being deleted.
+    impl Deletable for Item {
+        fn is_deletable(&self) -> bool {
+            self.0.is_multiple_of(2)
+        }
+
+        fn can_delete(&self) -> bool {
+            self.is_on() && self.is_deletable()
+        }
+    }
+
+    fn list(values: &[u32]) -> StatefulList<Item> {
+        let mut list = StatefulList::default();
+        for value in values {
+            list.push(Item(*value));
+        }
+        list
+    }
+
+    fn values(list: &StatefulList<Item>) -> Vec<u32> {
+        list.items().map(|item| item.0).collect()
+    }
+
+    fn visible(list: &StatefulList<Item>) -> Vec<u32> {
+        list.visible_items().map(|item| item.0).collect()
+    }
+
+    fn selected(list: &StatefulList<Item>) -> Option<u32> {
+        list.state.selected().map(|index| list.items[index].0)
+    }
+
+    #[test]
+    fn sort_by_keeps_the_selected_item() {
+        let mut list = list(&[30, 10, 20]);
+        list.state.select(Some(0));
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(values(&list), [10, 20, 30]);
+        assert_eq!(selected(&list), Some(30));
+    }
+
+    #[test]
+    fn sort_by_remaps_the_filter() {
+        let mut list = list(&[30, 10, 40, 20]);
+        list.apply_filter(|item| item.0 != 40);
+        list.state.select(Some(3));
+        list.sort_by(|a, b| b.0.cmp(&a.0));
+        assert_eq!(values(&list), [40, 30, 20, 10]);
+        assert_eq!(visible(&list), [30, 20, 10]);
+        assert_eq!(selected(&list), Some(20));
+        assert_eq!(list.selected_position(), Some(1));
+    }
+
+    #[test]
+    fn remove_where_keeps_the_selected_item() {
+        let mut list = list(&[10, 20, 30, 40]);
+        list.state.select(Some(2));
+        assert!(list.remove_where(|item| item.0 < 30));
+        assert_eq!(values(&list), [30, 40]);
+        assert_eq!(selected(&list), Some(30));
+    }
+
+    #[test]
+    fn remove_where_unselects_a_removed_item() {
+        let mut list = list(&[10, 20, 30]);
+        list.state.select(Some(1));
+        assert!(list.remove_where(|item| item.0 == 20));
+        assert_eq!(values(&list), [10, 30]);
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn remove_where_remaps_the_filter() {
+        let mut list = list(&[10, 20, 30, 40, 50]);
+        list.apply_filter(|item| item.0 != 30);
+        list.state.select(Some(4));
+        assert!(list.remove_where(|item| item.0 == 20 || item.0 == 30));
+        assert_eq!(values(&list), [10, 40, 50]);
+        assert_eq!(visible(&list), [10, 40, 50]);
+        assert_eq!(selected(&list), Some(50));
+        assert_eq!(list.selected_position(), Some(2));
+    }
+
+    #[test]
+    fn remove_where_without_a_match_changes_nothing() {
+        let mut list = list(&[10, 20]);
+        list.state.select(Some(1));
+        assert!(!list.remove_where(|item| item.0 == 30));
+        assert_eq!(values(&list), [10, 20]);
+        assert_eq!(selected(&list), Some(20));
+    }
+
+    #[test]
+    fn items_to_delete_under_all_skips_what_cant_be_deleted() {
+        let mut list = list(&[10, 11, 20, 30, 31]);
+        list.apply_filter(|item| item.0 != 30);
+        list.group_selection = Some(GroupSelection::All);
+        let items: Vec<u32> = list.items_to_delete().map(|item| item.0).collect();
+        assert_eq!(items, [10, 20]);
+    }
+}
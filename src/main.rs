@@ -1,23 +1,63 @@
+use clap::Parser;
+use delete_node_modules::actions::ActionState;
 use delete_node_modules::app::{App, AppResult};
+use delete_node_modules::cli::Cli;
+use delete_node_modules::config::Config;
+use delete_node_modules::deleter::DeleteQueue;
 use delete_node_modules::effects::walk_node_modules;
 use delete_node_modules::event_handling::{handle_event, lifecycle};
+use delete_node_modules::index::{Index, IndexEntry, ScanKey};
+use delete_node_modules::quarantine::Quarantine;
 use delete_node_modules::tui::Tui;
+use delete_node_modules::ui::format_size;
 use std::io;
 use std::sync::mpsc::channel;
+use std::sync::Arc;
 use std::time::Duration;
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 fn main() -> AppResult<()> {
+    // Parse the command line before touching the terminal.
+    let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref())?;
+    let mut scan_options = cli.scan_options(&config)?;
+
+    // Restore the previous scan of each root, unless asked to walk them again. Watching
+    // needs the walk to know which directories to watch.
+    let mut index = Index::load();
+    let roots = scan_options.roots.clone();
+    let key = ScanKey::of(&scan_options);
+    if !cli.rescan && !cli.watch {
+        for root in &roots {
+            if let Some(entries) = index.entries(root, &key) {
+                scan_options.cached.insert(root.clone(), entries.to_vec());
+            }
+        }
+    }
+
+    // Purge what a previous run left in quarantine, and what this one puts there.
+    let quarantine = Arc::new(Quarantine::load(config.quarantine.grace));
+    quarantine.start_purging();
+
     // Create an application.
-    let mut app = App::default();
+    let mut app = App {
+        show_project_names: cli.project_names,
+        delete_options: cli.delete_options(&config)?,
+        confirm_threshold: config.confirm,
+        scan_options: Some(scan_options.clone()),
+        delete_queue: Arc::new(DeleteQueue::new(quarantine)),
+        ..Default::default()
+    };
+
+    app.delete_queue.set_jobs_limit(cli.delete_jobs(&config));
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
     let (ui_sender, receiver) = channel();
     let handlers = vec![
-        walk_node_modules(ui_sender.clone()),
+        walk_node_modules(ui_sender.clone(), scan_options, app.scan_control.clone()),
         lifecycle(ui_sender.clone(), Duration::from_millis(250)),
     ];
     let mut tui = Tui::new(terminal, ui_sender, receiver, handlers);
@@ -33,5 +73,60 @@ fn main() -> AppResult<()> {
 
     // Exit the user interface.
     tui.exit()?;
+
+    // Remember what was found for the next start. An interrupted scan isn't complete
+    // enough to be trusted, and the index is only a cache, failing to write it is fine.
+    if app.list.done_scanning() {
+        for root in roots {
+            let entries = app
+                .list
+                .items()
+                .filter(|item| item.root == root)
+                .filter(|item| !matches!(item.delete_state, Some(ActionState::Done)))
+                .flat_map(|item| item.candidates())
+                .map(IndexEntry::from_item)
+                .collect();
+            let ignore_files = app
+                .ignore_files
+                .iter()
+                .filter(|path| path.starts_with(&root))
+                .cloned()
+                .collect();
+            index.update(root, key.clone(), ignore_files, entries);
+        }
+        let _ = index.save();
+    }
+
+    // Purging at exit would keep the terminal waiting, the next start purges the rest
+    // in the background.
+    let quarantine = app.delete_queue.quarantine();
+    quarantine.close();
+    let remaining = quarantine.remaining();
+    if remaining > 0 {
+        println!(
+            "{} directories left in quarantine, they are purged at the next start",
+            remaining
+        );
+    }
+
+    // Leave the last dry run report on the terminal.
+    if !app.dry_run_report.is_empty() {
+        let total = app.dry_run_report.iter().map(|(_, size)| size).sum();
+        println!(
+            "Dry run: would have removed {} directories, freeing {}",
+            app.dry_run_directories(),
+            format_size(total)
+        );
+        for (paths, size) in &app.dry_run_report {
+            for (i, path) in paths.iter().enumerate() {
+                let size = if i == 0 {
+                    format_size(*size)
+                } else {
+                    String::new()
+                };
+                println!("{:>10}  {}", size, path.display());
+            }
+        }
+    }
     Ok(())
 }
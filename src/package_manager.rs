@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The tool that installed a project's dependencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+/// Lockfiles, in the order they win when a project has several.
+const LOCKFILES: [(&str, PackageManager); 6] = [
+    ("pnpm-lock.yaml", PackageManager::Pnpm),
+    ("yarn.lock", PackageManager::Yarn),
+    ("bun.lockb", PackageManager::Bun),
+    ("bun.lock", PackageManager::Bun),
+    ("package-lock.json", PackageManager::Npm),
+    ("npm-shrinkwrap.json", PackageManager::Npm),
+];
+
+/// Files each manager leaves inside the `node_modules` it installs.
+const INSTALL_MARKERS: [(&str, PackageManager); 4] = [
+    (".pnpm", PackageManager::Pnpm),
+    (".yarn-state.yml", PackageManager::Yarn),
+    (".yarn-integrity", PackageManager::Yarn),
+    (".package-lock.json", PackageManager::Npm),
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct PackageJson {
+    package_manager: Option<String>,
+}
+
+impl PackageManager {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<PackageManager> {
+        match name {
+            "npm" => Some(PackageManager::Npm),
+            "yarn" => Some(PackageManager::Yarn),
+            "pnpm" => Some(PackageManager::Pnpm),
+            "bun" => Some(PackageManager::Bun),
+            _ => None,
+        }
+    }
+
+    /// Works out which manager installed `candidate`, part of the project at `project_root`.
+    ///
+    /// The `packageManager` field of `package.json` wins, then the lockfiles, then what
+    /// the install left inside the candidate, like pnpm's `.pnpm` virtual store.
+    pub fn detect(project_root: &Path, candidate: &Path) -> Option<PackageManager> {
+        Self::from_package_json(project_root)
+            .or_else(|| {
+                LOCKFILES
+                    .iter()
+                    .find(|(file, _)| project_root.join(file).exists())
+                    .map(|(_, manager)| *manager)
+            })
+            .or_else(|| {
+                INSTALL_MARKERS
+                    .iter()
+                    .find(|(file, _)| candidate.join(file).exists())
+                    .map(|(_, manager)| *manager)
+            })
+    }
+
+    /// Reads `"packageManager": "pnpm@8.15.0"` from `package.json`.
+    fn from_package_json(project_root: &Path) -> Option<PackageManager> {
+        let text = fs::read_to_string(project_root.join("package.json")).ok()?;
+        let package: PackageJson = serde_json::from_str(&text).ok()?;
+        let field = package.package_manager?;
+        let name = field.split('@').next()?;
+        Self::from_name(name)
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The `package.json` fields we care about.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PackageJson {
+    name: Option<String>,
+    version: Option<String>,
+    private: bool,
+}
+
+/// The project a candidate directory belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    /// Directory holding the `package.json`.
+    pub root: PathBuf,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub private: bool,
+}
+
+impl Project {
+    /// Looks for a `package.json` next to `path`.
+    ///
+    /// A manifest that can't be parsed still makes a project, just without metadata.
+    pub fn find(path: &Path) -> Option<Project> {
+        Self::at(path.parent()?)
+    }
+
+    /// The project whose `package.json` is in `root`.
+    pub fn at(root: &Path) -> Option<Project> {
+        let text = fs::read_to_string(root.join("package.json")).ok()?;
+        let package: PackageJson = serde_json::from_str(&text).unwrap_or_default();
+        Some(Project {
+            root: root.to_path_buf(),
+            name: package.name,
+            version: package.version,
+            private: package.private,
+        })
+    }
+
+    /// `name@version`, or whatever part of it is known.
+    pub fn label(&self) -> Option<String> {
+        match (&self.name, &self.version) {
+            (Some(name), Some(version)) => Some(format!("{}@{}", name, version)),
+            (Some(name), None) => Some(name.clone()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use crate::deleter::{remove_path, Removed};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Hidden directory, next to the quarantined trees' original place, that holds them.
+pub const QUARANTINE_DIR: &str = ".dnm-quarantine";
+
+/// How long quarantined trees can be restored, unless configured otherwise.
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(10 * 60);
+
+/// How often the purge looks for trees past their grace period.
+const PURGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tells quarantined trees of the same run apart.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A tree moved into quarantine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// The list entry it was deleted as, a workspace may have several trees.
+    item: PathBuf,
+    original: PathBuf,
+    quarantined: PathBuf,
+    since: SystemTime,
+    /// Left over by a previous run, purged right away.
+    #[serde(skip)]
+    leftover: bool,
+    /// Being purged, too late to restore.
+    #[serde(skip)]
+    purging: bool,
+    /// The purge failed, it is tried again at the next start.
+    #[serde(skip)]
+    failed: bool,
+}
+
+impl Entry {
+    fn is_restorable(&self) -> bool {
+        !self.leftover && !self.purging && !self.failed
+    }
+
+    fn is_due(&self, grace: Duration) -> bool {
+        !self.purging
+            && !self.failed
+            && (self.leftover || self.since.elapsed().is_ok_and(|elapsed| elapsed >= grace))
+    }
+}
+
+/// The pid in the name `isolate` gives a quarantined tree, `<name>.<pid>.<counter>`,
+/// and the name it had.
+fn parse_quarantined_name(name: &str) -> Option<(&str, u32)> {
+    let mut parts = name.rsplitn(3, '.');
+    parts.next()?.parse::<u64>().ok()?;
+    let pid = parts.next()?.parse().ok()?;
+    Some((parts.next()?, pid))
+}
+
+/// The record of one run's quarantined trees, `<pid>.json` in [`Record::dir`].
+///
+/// The run holds a lock on `<pid>.lock` next to it as long as it lives. Other runs leave
+/// the record, and the trees named after the pid, alone while it's held, and take them
+/// over once it's not.
+#[derive(Debug)]
+struct Record {
+    dir: PathBuf,
+    /// Held until the run ends.
+    _lock: File,
+}
+
+impl Record {
+    fn dir() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("delete-node-modules").join("quarantine"))
+    }
+
+    fn path(dir: &Path, pid: u32) -> PathBuf {
+        dir.join(format!("{}.json", pid))
+    }
+
+    fn lock_path(dir: &Path, pid: u32) -> PathBuf {
+        dir.join(format!("{}.lock", pid))
+    }
+
+    /// Locks the lock file of `pid`, `None` while the run it belongs to holds it.
+    fn try_lock(dir: &Path, pid: u32) -> io::Result<Option<File>> {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::lock_path(dir, pid))?;
+        match lock.try_lock() {
+            Ok(()) => Ok(Some(lock)),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Starts the record of this run.
+    fn create() -> io::Result<Record> {
+        let dir = Self::dir().ok_or(io::ErrorKind::NotFound)?;
+        fs::create_dir_all(&dir)?;
+        let lock = Self::try_lock(&dir, process::id())?.ok_or(io::ErrorKind::WouldBlock)?;
+        Ok(Record { dir, _lock: lock })
+    }
+
+    /// Whether the run `pid` still lives. Runs without a lock file are gone.
+    fn is_live(&self, pid: u32) -> bool {
+        if pid == process::id() {
+            return true;
+        }
+        match File::open(Self::lock_path(&self.dir, pid)) {
+            Ok(lock) => matches!(lock.try_lock(), Err(TryLockError::WouldBlock)),
+            Err(e) => e.kind() != io::ErrorKind::NotFound,
+        }
+    }
+
+    /// Takes over the trees recorded by runs that are gone. Their pids are returned with
+    /// their lock held, for their records to be removed once this run's has the trees.
+    fn adopt(&self) -> (Vec<Entry>, Vec<(u32, File)>) {
+        let mut entries = Vec::new();
+        let mut adopted: Vec<(u32, File)> = Vec::new();
+        let Ok(files) = fs::read_dir(&self.dir) else {
+            return (entries, adopted);
+        };
+        for file in files.filter_map(Result::ok) {
+            let path = file.path();
+            // Runs that recorded nothing leave only their lock file.
+            let Some(pid) = path
+                .extension()
+                .filter(|extension| *extension == "json" || *extension == "lock")
+                .and_then(|_| path.file_stem()?.to_str()?.parse::<u32>().ok())
+                .filter(|pid| *pid != process::id())
+                .filter(|pid| adopted.iter().all(|(adopted, _)| adopted != pid))
+            else {
+                continue;
+            };
+            let Ok(Some(lock)) = Self::try_lock(&self.dir, pid) else {
+                continue;
+            };
+            // Missing when another run took it over meanwhile.
+            if let Ok(text) = fs::read_to_string(Self::path(&self.dir, pid)) {
+                entries.extend(serde_json::from_str::<Vec<Entry>>(&text).unwrap_or_default());
+            }
+            adopted.push((pid, lock));
+        }
+        for entry in &mut entries {
+            entry.leftover = true;
+        }
+        (entries, adopted)
+    }
+
+    /// Records `entries`, replacing the previous record atomically.
+    fn save(&self, entries: &[Entry]) -> io::Result<()> {
+        let path = Self::path(&self.dir, process::id());
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(entries)?)?;
+        fs::rename(&temporary, &path)
+    }
+
+    /// Removes the record of the run `pid`.
+    fn remove(&self, pid: u32) {
+        let _ = fs::remove_file(Self::path(&self.dir, pid));
+        let _ = fs::remove_file(Self::lock_path(&self.dir, pid));
+    }
+}
+
+/// Deletes in two steps: trees are first renamed into a hidden directory next to them,
+/// which frees their place at once, then purged in the background once the grace
+/// period is over. Until then they can be renamed back.
+///
+/// Each run records its quarantined trees on disk. Those of a run that ended before
+/// purging them are purged by the next one, as are the trees the scan comes across in
+/// a quarantine directory that no live run recorded, in case one ended before it could.
+#[derive(Debug)]
+pub struct Quarantine {
+    entries: Mutex<Vec<Entry>>,
+    /// Quarantine directories to look through for trees nobody purges.
+    to_sweep: Mutex<Vec<PathBuf>>,
+    /// `None` when there's nowhere to record the trees, only this run purges them then.
+    record: Option<Record>,
+    pub grace: Duration,
+}
+
+impl Default for Quarantine {
+    fn default() -> Self {
+        Quarantine {
+            entries: Mutex::new(Vec::new()),
+            to_sweep: Mutex::new(Vec::new()),
+            record: None,
+            grace: DEFAULT_GRACE,
+        }
+    }
+}
+
+impl Quarantine {
+    /// Starts this run's record, taking over the trees of the runs that ended before
+    /// purging them.
+    pub fn load(grace: Duration) -> Quarantine {
+        let quarantine = Quarantine {
+            record: Record::create().ok(),
+            grace,
+            ..Default::default()
+        };
+        if let Some(record) = &quarantine.record {
+            let (entries, adopted) = record.adopt();
+            if !entries.is_empty() && record.save(&entries).is_err() {
+                // Their records stay for a run that can take them over.
+                return quarantine;
+            }
+            *quarantine.lock() = entries;
+            for (pid, _lock) in adopted {
+                record.remove(pid);
+            }
+        }
+        quarantine
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Entry>> {
+        self.entries.lock().expect("Quarantine lock poisoned.")
+    }
+
+    /// Records `entries`. Failing to is fine, the trees are only left behind until the
+    /// scan comes across them.
+    fn save(&self, entries: &[Entry]) {
+        if let Some(record) = &self.record {
+            let _ = record.save(entries);
+        }
+    }
+
+    /// Moves `path`, deleted as part of the list entry `item`, into quarantine.
+    pub fn isolate(&self, item: &Path, path: &Path) -> io::Result<()> {
+        let parent = path.parent().ok_or(io::ErrorKind::InvalidInput)?;
+        let name = path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+        let dir = parent.join(QUARANTINE_DIR);
+        let mut quarantined_name = name.to_os_string();
+        quarantined_name.push(format!(
+            ".{}.{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let quarantined = dir.join(quarantined_name);
+        // The purge removes the directory once it's empty, holding the lock too.
+        let mut entries = self.lock();
+        fs::create_dir_all(&dir)?;
+        fs::rename(path, &quarantined)?;
+        entries.push(Entry {
+            item: item.to_path_buf(),
+            original: path.to_path_buf(),
+            quarantined,
+            since: SystemTime::now(),
+            leftover: false,
+            purging: false,
+            failed: false,
+        });
+        self.save(&entries);
+        Ok(())
+    }
+
+    /// Has the quarantine directory `dir`, that the scan came across, looked through for
+    /// trees left by runs that are gone.
+    pub fn sweep(&self, dir: PathBuf) {
+        self.to_sweep
+            .lock()
+            .expect("Quarantine sweep lock poisoned.")
+            .push(dir);
+    }
+
+    /// Takes over the trees in the directories to sweep that no live run recorded.
+    fn sweep_pending(&self) {
+        let dirs = std::mem::take(
+            &mut *self
+                .to_sweep
+                .lock()
+                .expect("Quarantine sweep lock poisoned."),
+        );
+        // Without a record, there's no telling which runs are live.
+        let Some(record) = &self.record else {
+            return;
+        };
+        for dir in dirs {
+            let (Ok(trees), Some(parent)) = (fs::read_dir(&dir), dir.parent()) else {
+                continue;
+            };
+            let mut entries = self.lock();
+            let mut swept = false;
+            for tree in trees.filter_map(Result::ok) {
+                let quarantined = tree.path();
+                let file_name = tree.file_name();
+                let Some((name, pid)) = file_name.to_str().and_then(parse_quarantined_name) else {
+                    // Not put there by `isolate`.
+                    continue;
+                };
+                if record.is_live(pid)
+                    || entries.iter().any(|entry| entry.quarantined == quarantined)
+                {
+                    continue;
+                }
+                let original = parent.join(name);
+                entries.push(Entry {
+                    item: original.clone(),
+                    original,
+                    quarantined,
+                    since: SystemTime::now(),
+                    leftover: true,
+                    purging: false,
+                    failed: false,
+                });
+                swept = true;
+            }
+            if swept {
+                self.save(&entries);
+            }
+        }
+    }
+
+    /// Whether the trees of the list entry `item` can still be restored.
+    pub fn is_quarantined(&self, item: &Path) -> bool {
+        self.lock()
+            .iter()
+            .any(|entry| entry.item == item && entry.is_restorable())
+    }
+
+    /// Trees not purged yet.
+    pub fn remaining(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Renames the trees of the list entry `item` back to their place.
+    ///
+    /// Fails when something took their place meanwhile, trees already restored stay so.
+    pub fn restore(&self, item: &Path) -> io::Result<()> {
+        let mut entries = self.lock();
+        if !entries
+            .iter()
+            .any(|entry| entry.item == item && entry.is_restorable())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no longer in quarantine",
+            ));
+        }
+        let result = entries
+            .iter()
+            .filter(|entry| entry.item == item && entry.is_restorable())
+            .try_for_each(|entry| {
+                if fs::symlink_metadata(&entry.original).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} exists again", entry.original.display()),
+                    ));
+                }
+                fs::rename(&entry.quarantined, &entry.original)?;
+                remove_if_empty(&entry.quarantined);
+                Ok(())
+            });
+        entries
+            .retain(|entry| entry.item != item || fs::symlink_metadata(&entry.quarantined).is_ok());
+        self.save(&entries);
+        result
+    }
+
+    /// Purges the trees in quarantine for `grace` or longer.
+    fn purge_due(&self, grace: Duration) {
+        let due: Vec<PathBuf> = self
+            .lock()
+            .iter_mut()
+            .filter(|entry| entry.is_due(grace))
+            .map(|entry| {
+                entry.purging = true;
+                entry.quarantined.clone()
+            })
+            .collect();
+        for quarantined in due {
+            let result = match fs::symlink_metadata(&quarantined) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                _ => remove_path(&quarantined, &mut Removed::default(), &mut |_| {}, &|| true),
+            };
+            let mut entries = self.lock();
+            match result {
+                Ok(()) => {
+                    remove_if_empty(&quarantined);
+                    entries.retain(|entry| entry.quarantined != quarantined);
+                }
+                Err(_) => {
+                    for entry in entries
+                        .iter_mut()
+                        .filter(|entry| entry.quarantined == quarantined)
+                    {
+                        entry.purging = false;
+                        entry.failed = true;
+                    }
+                }
+            }
+            self.save(&entries);
+        }
+    }
+
+    /// Ends the run without waiting for the trees still in quarantine: they stay
+    /// recorded, for the next start to purge in the background. Without any, the record
+    /// of this run goes away.
+    pub fn close(&self) {
+        if let Some(record) = self.record.as_ref().filter(|_| self.lock().is_empty()) {
+            record.remove(process::id());
+        }
+    }
+
+    /// Purges quarantined trees in the background, at low priority, as their grace
+    /// period ends.
+    pub fn start_purging(self: &Arc<Self>) -> thread::JoinHandle<()> {
+        let quarantine = Arc::clone(self);
+        thread::spawn(move || {
+            lower_priority();
+            loop {
+                quarantine.sweep_pending();
+                quarantine.purge_due(quarantine.grace);
+                thread::sleep(PURGE_INTERVAL);
+            }
+        })
+    }
+}
+
+/// Removes the quarantine directory holding `quarantined` once it's empty.
+fn remove_if_empty(quarantined: &Path) {
+    if let Some(dir) = quarantined.parent() {
+        // Fails while other trees are quarantined there.
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// Makes the calling thread yield the CPU and the disk to everything else.
+#[cfg(target_os = "linux")]
+fn lower_priority() {
+    /// `IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0)`.
+    const IOPRIO_IDLE: libc::c_int = 3 << 13;
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    // SAFETY: both calls only change the scheduling of the calling thread, and failing
+    // leaves it as it was.
+    unsafe {
+        let tid = libc::gettid();
+        libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, 19);
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, IOPRIO_IDLE);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lower_priority() {}
@@ -0,0 +1,66 @@
+use crate::event::{DirSearch, Event};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Condvar, Mutex};
+
+/// Lets the UI pause, resume and cancel a scan.
+///
+/// The walker and the sizing workers check it as they go. Everything a scan reports
+/// goes through [`ScanControl::send`], so once [`ScanControl::cancel`] returns, nothing
+/// more from that scan reaches the channel.
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    /// Held while sending, and to wait for the scan to be resumed.
+    lock: Mutex<()>,
+    resumed: Condvar,
+}
+
+impl ScanControl {
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        let _guard = self.lock.lock().expect("Scan control lock poisoned.");
+        self.paused.store(false, Ordering::SeqCst);
+        self.resumed.notify_all();
+    }
+
+    pub fn cancel(&self) {
+        let _guard = self.lock.lock().expect("Scan control lock poisoned.");
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Blocks while the scan is paused, then tells whether it should go on.
+    pub fn proceed(&self) -> bool {
+        if self.is_paused() {
+            let guard = self.lock.lock().expect("Scan control lock poisoned.");
+            let _guard = self
+                .resumed
+                .wait_while(guard, |_| self.is_paused() && !self.is_cancelled())
+                .expect("Scan control lock poisoned.");
+        }
+        !self.is_cancelled()
+    }
+
+    /// Reports `event` unless the scan was cancelled.
+    pub fn send(&self, sender: &Sender<Event>, event: DirSearch) {
+        let _guard = self.lock.lock().expect("Scan control lock poisoned.");
+        if !self.is_cancelled() {
+            sender
+                .send(Event::Search(event))
+                .expect("Unable to send data through the channel.");
+        }
+    }
+}
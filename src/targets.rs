@@ -0,0 +1,189 @@
+use crate::app::AppResult;
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// A kind of directory the scanner can offer for deletion.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TargetProfile {
+    /// Name used to pick the profile from the command line or the config.
+    pub name: String,
+    /// Directory name or glob the candidate must match.
+    pub pattern: String,
+    /// Glob for a file that must sit next to the candidate, e.g. `Cargo.toml` for `target`.
+    #[serde(default)]
+    pub marker: Option<String>,
+    /// Label shown in the list.
+    pub ecosystem: String,
+    /// Paths, relative to the candidate's parent, deleted instead of the candidate itself.
+    /// Lets a profile offer several artifacts as one removable unit.
+    #[serde(default)]
+    pub parts: Vec<String>,
+}
+
+impl TargetProfile {
+    fn new(name: &str, pattern: &str, marker: Option<&str>, ecosystem: &str) -> Self {
+        TargetProfile {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            marker: marker.map(str::to_string),
+            ecosystem: ecosystem.to_string(),
+            parts: Vec::new(),
+        }
+    }
+
+    fn with_parts(mut self, parts: &[&str]) -> Self {
+        self.parts = parts.iter().map(|part| part.to_string()).collect();
+        self
+    }
+
+    /// The paths deleting `candidate` removes: the existing `parts`, or the candidate.
+    pub fn removable_paths(&self, candidate: &Path) -> Vec<PathBuf> {
+        match candidate.parent() {
+            Some(parent) if !self.parts.is_empty() => self
+                .parts
+                .iter()
+                .map(|part| parent.join(part))
+                .filter(|path| fs::symlink_metadata(path).is_ok())
+                .collect(),
+            _ => vec![candidate.to_path_buf()],
+        }
+    }
+}
+
+/// Ecosystem label of the Node.js profiles.
+pub const NODE_ECOSYSTEM: &str = "Node.js";
+
+/// The profiles shipped with the tool.
+pub fn builtin_profiles() -> Vec<TargetProfile> {
+    vec![
+        TargetProfile::new("node", "node_modules", None, NODE_ECOSYSTEM),
+        // Yarn Plug'n'Play keeps packages in `.yarn` next to its loader. Releases and
+        // plugins in `.yarn` are part of the project and stay.
+        TargetProfile::new("yarn-pnp", ".yarn", Some(".pnp.cjs"), NODE_ECOSYSTEM).with_parts(&[
+            ".yarn/cache",
+            ".yarn/unplugged",
+            ".yarn/install-state.gz",
+            ".pnp.cjs",
+            ".pnp.loader.mjs",
+        ]),
+        TargetProfile::new("rust", "target", Some("Cargo.toml"), "Rust"),
+        TargetProfile::new("python-venv", ".venv", None, "Python"),
+        TargetProfile::new("pycache", "__pycache__", None, "Python"),
+        TargetProfile::new(
+            "gradle",
+            ".gradle",
+            Some("{build,settings}.gradle{,.kts}"),
+            "Gradle",
+        ),
+        TargetProfile::new(
+            "gradle-build",
+            "build",
+            Some("build.gradle{,.kts}"),
+            "Gradle",
+        ),
+        TargetProfile::new("cocoapods", "Pods", Some("Podfile"), "CocoaPods"),
+        TargetProfile::new("elixir-build", "_build", Some("mix.exs"), "Elixir"),
+        TargetProfile::new("elixir-deps", "deps", Some("mix.exs"), "Elixir"),
+    ]
+}
+
+/// The profiles used when neither the command line nor the config picks any.
+pub const DEFAULT_TARGETS: [&str; 2] = ["node", "yarn-pnp"];
+
+#[derive(Debug, Clone)]
+struct CompiledProfile {
+    profile: TargetProfile,
+    pattern: GlobMatcher,
+    marker: Option<GlobMatcher>,
+}
+
+/// The set of profiles the walker matches directories against.
+#[derive(Debug, Clone)]
+pub struct Targets {
+    profiles: Vec<CompiledProfile>,
+}
+
+fn compile(glob: &str) -> AppResult<GlobMatcher> {
+    Ok(Glob::new(glob)
+        .map_err(|e| format!("invalid pattern `{}`: {}", glob, e))?
+        .compile_matcher())
+}
+
+impl Targets {
+    /// Picks the profiles named in `names` among the built-in and `custom` profiles.
+    ///
+    /// A name matches a profile's name or, case-insensitively, its ecosystem label, so
+    /// `python` selects every Python profile.
+    pub fn resolve(names: &[String], custom: &[TargetProfile]) -> AppResult<Targets> {
+        let available: Vec<TargetProfile> = builtin_profiles()
+            .into_iter()
+            .chain(custom.iter().cloned())
+            .collect();
+        let mut profiles: Vec<CompiledProfile> = Vec::new();
+        for name in names {
+            let matching: Vec<&TargetProfile> = available
+                .iter()
+                .filter(|p| p.name == *name || p.ecosystem.eq_ignore_ascii_case(name))
+                .collect();
+            if matching.is_empty() {
+                let known: Vec<&str> = available.iter().map(|p| p.name.as_str()).collect();
+                return Err(format!(
+                    "unknown target `{}`, expected one of: {}",
+                    name,
+                    known.join(", ")
+                )
+                .into());
+            }
+            for profile in matching {
+                if profiles.iter().any(|p| p.profile.name == profile.name) {
+                    continue;
+                }
+                profiles.push(CompiledProfile {
+                    profile: profile.clone(),
+                    pattern: compile(&profile.pattern)?,
+                    marker: profile.marker.as_deref().map(compile).transpose()?,
+                });
+            }
+        }
+        Ok(Targets { profiles })
+    }
+
+    /// Names of the selected profiles.
+    pub fn names(&self) -> Vec<String> {
+        self.profiles
+            .iter()
+            .map(|p| p.profile.name.clone())
+            .collect()
+    }
+
+    /// Returns the profile the directory `entry` is a candidate for, if any.
+    pub fn matching(&self, entry: &DirEntry) -> Option<&TargetProfile> {
+        if !entry.file_type().is_dir() {
+            return None;
+        }
+        let name = Path::new(entry.file_name());
+        self.profiles
+            .iter()
+            .filter(|p| p.pattern.is_match(name))
+            .find(|p| {
+                p.marker
+                    .as_ref()
+                    .is_none_or(|marker| has_sibling(entry.path(), marker))
+            })
+            .map(|p| &p.profile)
+    }
+}
+
+/// Whether a file matching `marker` sits in the same directory as `path`.
+fn has_sibling(path: &Path, marker: &GlobMatcher) -> bool {
+    path.parent()
+        .and_then(|parent| fs::read_dir(parent).ok())
+        .is_some_and(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| entry.path() != path && marker.is_match(entry.file_name()))
+        })
+}
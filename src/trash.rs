@@ -0,0 +1,190 @@
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// The sticky bit, required on an administrator created `$topdir/.Trash`.
+const STICKY: u32 = 0o1000;
+
+/// A trash directory, with the `files` and `info` directories in it.
+struct TrashDir {
+    path: PathBuf,
+    /// Directory the paths in the `.trashinfo` files are relative to, if they are.
+    base: Option<PathBuf>,
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid has no preconditions and can't fail.
+    unsafe { libc::getuid() }
+}
+
+/// Creates `path` as a directory only its owner can get into, unless it's there already.
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    match DirBuilder::new().mode(0o700).create(path) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether `path` is a directory, not a symlink, owned by the current user.
+fn is_own_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir() && metadata.uid() == uid())
+}
+
+/// The top directory of the filesystem holding `path`, whose device is `device`.
+fn mount_top(path: &Path, device: u64) -> PathBuf {
+    let mut top = path;
+    while let Some(parent) = top.parent() {
+        match fs::symlink_metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => top = parent,
+            _ => break,
+        }
+    }
+    top.to_path_buf()
+}
+
+/// `$XDG_DATA_HOME/Trash`, if it is on `device`.
+///
+/// It's only created once known to be on `device`: directories missing below its
+/// nearest existing ancestor would be on the same one.
+fn home_trash(device: u64) -> Option<TrashDir> {
+    let path = dirs::data_dir()?.join("Trash");
+    let (existing, metadata) = path
+        .ancestors()
+        .find_map(|dir| Some((dir, fs::metadata(dir).ok()?)))?;
+    if metadata.dev() != device {
+        return None;
+    }
+    if existing != path {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&path)
+            .ok()?;
+    }
+    Some(TrashDir { path, base: None })
+}
+
+/// `$topdir/.Trash/$uid` when an administrator set up `$topdir/.Trash`, or
+/// `$topdir/.Trash-$uid` otherwise.
+fn top_trash(top: &Path) -> Option<TrashDir> {
+    let shared = top.join(".Trash");
+    let shared_ok = fs::symlink_metadata(&shared)
+        .is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & STICKY != 0);
+    if shared_ok {
+        let path = shared.join(uid().to_string());
+        if create_private_dir(&path).is_ok() && is_own_dir(&path) {
+            return Some(TrashDir {
+                path,
+                base: Some(top.to_path_buf()),
+            });
+        }
+    }
+    let path = top.join(format!(".Trash-{}", uid()));
+    (create_private_dir(&path).is_ok() && is_own_dir(&path)).then(|| TrashDir {
+        path,
+        base: Some(top.to_path_buf()),
+    })
+}
+
+/// Escapes `path` the way URIs are, as the `Path` key requires.
+fn escape(path: &Path) -> String {
+    let mut escaped = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                escaped.push(byte as char)
+            }
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    escaped
+}
+
+/// The current local time, as `DeletionDate` wants it.
+fn deletion_date() -> String {
+    // SAFETY: localtime_r only writes to the given struct, which is plain data.
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// Writes the `.trashinfo` file for `path`, under a name no other trashed file has.
+///
+/// Returns the name, which the trashed file takes in `files`.
+fn write_info(trash: &TrashDir, path: &Path) -> io::Result<OsString> {
+    let file_name = path.file_name().unwrap_or(path.as_os_str());
+    let original = match &trash.base {
+        Some(base) => path.strip_prefix(base).unwrap_or(path),
+        None => path,
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        escape(original),
+        deletion_date()
+    );
+    for n in 1.. {
+        let mut name = file_name.to_os_string();
+        if n > 1 {
+            name.push(format!(".{}", n));
+        }
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        // Creating the info file claims the name.
+        let created = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(trash.path.join("info").join(info_name));
+        match created {
+            Ok(mut file) => {
+                file.write_all(info.as_bytes())?;
+                return Ok(name);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("Ran out of names in the trash.")
+}
+
+/// Moves `path` to the trash of its filesystem, following the freedesktop.org Trash
+/// specification.
+///
+/// The home trash is used when it's on the same filesystem, the filesystem's own trash
+/// otherwise. Fails with [`io::ErrorKind::CrossesDevices`] when no trash can be used on
+/// that filesystem, moving there would mean copying.
+pub fn move_to_trash(path: &Path) -> io::Result<()> {
+    // The parent only, a symlink is trashed rather than what it points to.
+    let path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize()?.join(name),
+        _ => path.canonicalize()?,
+    };
+    let device = fs::symlink_metadata(&path)?.dev();
+    let trash = home_trash(device)
+        .or_else(|| top_trash(&mount_top(&path, device)))
+        .ok_or_else(|| io::Error::from(io::ErrorKind::CrossesDevices))?;
+    fs::create_dir_all(trash.path.join("files"))?;
+    fs::create_dir_all(trash.path.join("info"))?;
+    let name = write_info(&trash, &path)?;
+    let mut info_name = name.clone();
+    info_name.push(".trashinfo");
+    if let Err(e) = fs::rename(&path, trash.path.join("files").join(&name)) {
+        let _ = fs::remove_file(trash.path.join("info").join(info_name));
+        return Err(e);
+    }
+    Ok(())
+}
@@ -1,17 +1,23 @@
 use crate::{
-    app::{App, GroupSelection},
+    actions::ActionState,
+    app::{App, Confirmation, GroupSelection, CONFIRM_WORD},
+    breakdown::PartSize,
+    deleter::Removed,
+    dir_entry_item::DirEntryItem,
     list::Filterable,
 };
+use std::cmp::Reverse;
+use std::time::Duration;
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::*,
     Frame,
 };
 
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     const KIB: u64 = 1024;
     const MIB: u64 = KIB * 1024;
     const GIB: u64 = MIB * 1024;
@@ -37,16 +43,191 @@ fn format_number(n: u64) -> String {
     result
 }
 
+fn format_age(age: Duration) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = MINUTE * 60;
+    const DAY: u64 = HOUR * 24;
+    const MONTH: u64 = DAY * 30;
+    const YEAR: u64 = DAY * 365;
+
+    let secs = age.as_secs();
+    let (count, unit) = if secs >= YEAR {
+        (secs / YEAR, "year")
+    } else if secs >= MONTH {
+        (secs / MONTH, "month")
+    } else if secs >= DAY {
+        (secs / DAY, "day")
+    } else if secs >= HOUR {
+        (secs / HOUR, "hour")
+    } else if secs >= MINUTE {
+        (secs / MINUTE, "minute")
+    } else {
+        return "just now".to_string();
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+/// Parts listed per section of a breakdown, the rest is summed up on one line.
+const BREAKDOWN_PARTS: usize = 20;
+
+/// Lines of one section of an item's breakdown.
+fn part_lines(heading: &str, parts: &[PartSize], total: u64) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!("      {}", heading))];
+    for part in parts.iter().take(BREAKDOWN_PARTS) {
+        let share = if total > 0 {
+            part.size as f64 * 100.0 / total as f64
+        } else {
+            0.0
+        };
+        lines.push(Line::from(format!(
+            "        {:>10} {:>5.1}%  {}",
+            format_size(part.size),
+            share,
+            part.name.display()
+        )));
+    }
+    if parts.len() > BREAKDOWN_PARTS {
+        let rest = &parts[BREAKDOWN_PARTS..];
+        lines.push(Line::from(format!(
+            "        {:>10}         and {} more",
+            format_size(rest.iter().map(|part| part.size).sum()),
+            format_number(rest.len() as u64)
+        )));
+    }
+    lines
+}
+
+/// Lines shown under an expanded item.
+fn breakdown_lines(item: &DirEntryItem) -> Vec<Line<'static>> {
+    match (&item.breakdown_state, &item.breakdown) {
+        (Some(ActionState::Done), Some(breakdown)) => {
+            let mut lines = part_lines("Top-level entries", &breakdown.packages, item.size);
+            if breakdown.nested.is_empty() {
+                lines.push(Line::from("      No nested node_modules"));
+            } else {
+                lines.extend(part_lines(
+                    "Nested node_modules",
+                    &breakdown.nested,
+                    item.size,
+                ));
+            }
+            lines
+        }
+        (Some(ActionState::Failed(e)), _) => {
+            vec![Line::from(format!("      breakdown unavailable ({})", e))]
+        }
+        _ => vec![Line::from("      computing breakdown…")],
+    }
+}
+
+/// Cells of the progress bar shown on rows being deleted.
+const PROGRESS_BAR_WIDTH: usize = 20;
+
+/// A progress bar for an item being deleted, with what was removed so far.
+///
+/// Removing a file that keeps links elsewhere frees nothing, so the bar goes up to what
+/// the item alone frees.
+fn progress_text(item: &DirEntryItem) -> String {
+    if matches!(item.delete_state, Some(ActionState::Queued)) {
+        return "[queued]".to_string();
+    }
+    let ratio = if item.exclusive_size > 0 {
+        (item.removed.bytes as f64 / item.exclusive_size as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let filled = (ratio * PROGRESS_BAR_WIDTH as f64).round() as usize;
+    format!(
+        "[{}{}] {:>3.0}% ({} files)",
+        "█".repeat(filled),
+        "░".repeat(PROGRESS_BAR_WIDTH - filled),
+        ratio * 100.0,
+        format_number(item.removed.files)
+    )
+}
+
+/// `1h 02m`, `3m 05s` or `12s`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// The project name when asked for and known, the path relative to the root otherwise.
+fn display_name(item: &DirEntryItem, project_names: bool) -> String {
+    item.project
+        .as_ref()
+        .filter(|_| project_names)
+        .and_then(|project| project.label())
+        .unwrap_or_else(|| item.relative_path().display().to_string())
+}
+
+/// Lines shown under an expanded workspace, one per member.
+fn member_lines(group: &DirEntryItem) -> Vec<Line<'static>> {
+    group
+        .members
+        .iter()
+        .map(|member| {
+            let name = member
+                .entry
+                .path()
+                .strip_prefix(group.entry.path())
+                .unwrap_or(member.entry.path())
+                .display();
+            let line = match &member.size_state {
+                ActionState::Done => {
+                    let share = if group.size > 0 {
+                        member.size as f64 * 100.0 / group.size as f64
+                    } else {
+                        0.0
+                    };
+                    format!(
+                        "      {:>10} {:>5.1}%  {}",
+                        format_size(member.size),
+                        share,
+                        name
+                    )
+                }
+                ActionState::Failed(e) => {
+                    format!("      {:>10}         {} ({})", "", name, e)
+                }
+                _ => format!("      {:>10}         {}", "computing…", name),
+            };
+            Line::from(line)
+        })
+        .collect()
+}
+
 /// Renders the user interface widgets.
 pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
-            Constraint::Length(6), // Fixed size for the header
-            Constraint::Min(0),    // Takes up the rest of the space
-            Constraint::Length(3), // For status/feedback
+            Constraint::Length(10), // Fixed size for the header
+            Constraint::Min(0),     // Takes up the rest of the space
+            Constraint::Length(3),  // For status/feedback
         ])
         .split(frame.size());
+    // The dry run report, the failed deletions or the scan errors panel takes the bottom
+    // of the list area while it's open.
+    let (list_area, panel_area) = if app.show_report || app.show_failures || app.show_errors {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[1]);
+        (areas[0], Some(areas[1]))
+    } else {
+        (chunks[1], None)
+    };
     // This is where you add new widgets.
     // See the following resources:
     // - https://docs.rs/ratatui/latest/ratatui/widgets/index.html
@@ -55,9 +236,13 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
         Paragraph::new(
             "\
             Press `Esc`, `Ctrl-C` or `q` to stop running.\n\
-            Press `up` and `down` to navigate and `space` to toggle selection\n\
+            Press `up` and `down` to navigate, `space` to toggle selection and `d` to show details\n\
             Press `a` or `Tab` to toggle selection between all, none or per item\n\
-            Press `Enter` to delete currently selected items\n\
+            Press `p` to toggle project names, `s` to change the sort order and `o` to filter by age\n\
+            Press `z` to pause or resume the scan, `x` to cancel it, `r` to scan again and `e` to show errors\n\
+            Press `Enter` to delete currently selected items, `u` to pause or resume deleting and `c` to cancel it\n\
+            Press `+` and `-` to change how many are deleted at once, `n` to toggle dry run and `v` to show its report\n\
+            Press `b` to restore the selected directory from quarantine, `f` to show failed deletions and `t` to retry them\n\
         "
             .to_string(),
         )
@@ -73,22 +258,17 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
         chunks[0],
     );
 
-    if app.list.has_visible_items() {
-        let filter_input = app.filter_input.as_ref();
-        let has_search_input = filter_input.map(|s| !s.is_empty()).unwrap_or(false);
+    if !app.list.is_empty() {
+        let has_search_input = app
+            .filter_input
+            .as_ref()
+            .map(|s| !s.is_empty())
+            .unwrap_or(false);
 
         let items: Vec<ListItem> = app
             .list
             .visible_items()
-            .filter(|item| {
-                if let Some(filter_input) = filter_input {
-                    item.entry.path().to_str().unwrap().contains(filter_input)
-                } else {
-                    true
-                }
-            })
-            // .filter(|item| item.entry.path().contains(&filter_input))
-            .map(|item: &crate::dir_entry_item::DirEntryItem| {
+            .map(|item: &DirEntryItem| {
                 let mut is_on = false;
                 if has_search_input {
                     is_on = false;
@@ -98,10 +278,42 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
                     is_on = true;
                 }
 
+                let size_text = match &item.size_state {
+                    ActionState::Pending => "computing…".to_string(),
+                    ActionState::Failed(e) => format!("size unavailable ({})", e),
+                    _ if item.shared_size() > 0 => format!(
+                        "{} in {} files, {} exclusive",
+                        format_size(item.size),
+                        format_number(item.files),
+                        format_size(item.exclusive_size)
+                    ),
+                    _ => format!(
+                        "{} in {} files",
+                        format_size(item.size),
+                        format_number(item.files)
+                    ),
+                };
+                let name = display_name(item, app.show_project_names);
+                let age_text = item
+                    .age()
+                    .map(|age| format!(", active {}", format_age(age)))
+                    .unwrap_or_default();
+                let kind = match &item.workspace {
+                    Some(workspace) if item.is_group() => format!(
+                        "{} {}, {} node_modules",
+                        item.ecosystem,
+                        workspace.label(),
+                        item.members.len()
+                    ),
+                    _ => item.ecosystem.to_string(),
+                };
                 let title = format!(
-                    "{} - {}",
-                    item.entry.path().display(),
-                    format_size(item.size)
+                    "[{}] {} ({}) - {}{}",
+                    item.root.display(),
+                    name,
+                    kind,
+                    size_text,
+                    age_text
                 );
 
                 let select_char = if item.is_deleting() {
@@ -111,20 +323,99 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
                 } else {
                     "[ ] "
                 };
-                ListItem::new(Line::from(vec![Span::raw(select_char), Span::raw(title)]))
-                    .style(Style::default().fg(Color::Black).bg(Color::White))
+                let manager = item
+                    .package_manager
+                    .map(|manager| manager.label())
+                    .unwrap_or("");
+                let mut spans = vec![
+                    Span::raw(select_char),
+                    Span::raw(format!("{:<5}", manager)),
+                    Span::raw(title),
+                ];
+                if item.is_orphaned() {
+                    spans.push(Span::styled(" [orphaned]", Style::default().fg(Color::Red)));
+                }
+                if matches!(item.delete_state, Some(ActionState::PartiallyDeleted)) {
+                    spans.push(Span::styled(
+                        " [partially deleted, reinstall needed]",
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if matches!(item.delete_state, Some(ActionState::Done))
+                    && app
+                        .delete_queue
+                        .quarantine()
+                        .is_quarantined(item.entry.path())
+                {
+                    spans.push(Span::styled(
+                        " [quarantined, `b` to restore]",
+                        Style::default().fg(Color::Blue),
+                    ));
+                }
+                if let Some(reason) = &item.permanent {
+                    spans.push(Span::styled(
+                        format!(" [deleted permanently: {}]", reason),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if let Some(ActionState::Failed(kind)) = &item.delete_state {
+                    // Failing midway leaves the project as broken as cancelling does.
+                    let partially = if item.removed != Removed::default() {
+                        ", partially deleted, reinstall needed"
+                    } else {
+                        ""
+                    };
+                    spans.push(Span::styled(
+                        format!(" [{}{}, `f` for details]", kind, partially),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if item.is_deleting() {
+                    spans.push(Span::styled(
+                        format!(" {}", progress_text(item)),
+                        Style::default().fg(Color::Blue),
+                    ));
+                }
+                let mut lines = vec![Line::from(spans)];
+                if item.expanded && item.is_group() {
+                    lines.extend(member_lines(item));
+                } else if item.expanded {
+                    lines.extend(breakdown_lines(item));
+                }
+                ListItem::new(lines).style(Style::default().fg(Color::Black).bg(Color::White))
             })
             .collect();
 
         // Create a List from all list items and highlight the currently selected one
-        let middle_text = if app.list.is_scanning() {
+        let sizing = app
+            .list
+            .visible_items()
+            .filter(|item| item.is_sizing())
+            .count();
+        let middle_text = if app.scan_control.is_paused() {
+            ", Paused".to_string()
+        } else if app.list.is_scanning() {
             ", Scanning...".to_string()
+        } else if sizing > 0 {
+            format!(", Sizing {}...", sizing)
         } else if app.deleting_size.current.count > 0 {
+            let verb = if app.delete_options.dry_run {
+                "Dry run deleting"
+            } else if app.delete_options.trash {
+                "Moving to trash"
+            } else if app.delete_options.quarantine {
+                "Quarantining"
+            } else {
+                "Deleting"
+            };
             format!(
-                "Deleting {} ({})",
+                "{} {} ({})",
+                verb,
                 app.deleting_size.current.count,
                 format_size(app.deleting_size.current.total_size)
             )
+        } else if app.scan_control.is_cancelled() {
+            ", Scan cancelled".to_string()
         } else {
             "".to_string()
         };
@@ -138,13 +429,57 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
         } else {
             "".to_string()
         };
+        let view_text = match (app.sort, app.min_age) {
+            (Some(sort), Some(min_age)) => format!(
+                " [sorted by {}, inactive for {}]",
+                sort.label(),
+                min_age.label()
+            ),
+            (Some(sort), None) => format!(" [sorted by {}]", sort.label()),
+            (None, Some(min_age)) => format!(" [inactive for {}]", min_age.label()),
+            (None, None) => "".to_string(),
+        };
+        let skipped_text = if app.skipped_mounts > 0 {
+            format!(
+                " ({} mount points skipped)",
+                format_number(app.skipped_mounts)
+            )
+        } else {
+            "".to_string()
+        };
+        let errors_text = if app.scan_errors.is_empty() {
+            "".to_string()
+        } else {
+            format!(
+                " ({} inaccessible, `e` to show)",
+                format_number(app.scan_errors.len() as u64)
+            )
+        };
+        let dry_run_text = if app.delete_options.dry_run {
+            " [dry run]"
+        } else if app.delete_options.trash {
+            " [to trash]"
+        } else if app.delete_options.quarantine {
+            " [quarantine]"
+        } else {
+            ""
+        };
+        let restore_text = match &app.restore_error {
+            Some(e) => format!(" (restore failed: {})", e),
+            None => "".to_string(),
+        };
         let title = format!(
-            "Directories {}/{} {} Volume :{} --{}",
+            "Directories {}/{} {} Volume :{} --{}{}{}{}{}{}",
             selected_number_text,
             items.len(),
             middle_text,
             selection_size_text,
-            search_text
+            dry_run_text,
+            search_text,
+            view_text,
+            skipped_text,
+            errors_text,
+            restore_text
         );
         let list = List::new(items)
             .block(
@@ -156,8 +491,47 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
             .highlight_style(Style::default().fg(Color::Black).bg(Color::White))
             .highlight_symbol(">> ");
 
-        // We can now render the item list
-        frame.render_stateful_widget(list, chunks[1], &mut app.list.state);
+        // We can now render the item list. The list state holds indices into all items,
+        // the widget wants the position among the visible ones.
+        let mut state = ListState::default()
+            .with_offset(app.list.state.offset())
+            .with_selected(app.list.selected_position());
+        frame.render_stateful_widget(list, list_area, &mut state);
+        *app.list.state.offset_mut() = state.offset();
+
+        let deletion = app
+            .deletion
+            .as_ref()
+            .filter(|_| !app.is_in_search_mode && app.deleting_size.current.count > 0);
+        if let Some(deletion) = deletion {
+            let eta = deletion
+                .eta()
+                .map(|eta| format!(", {} left", format_duration(eta)))
+                .unwrap_or_default();
+            let label = format!(
+                "{} of {}, {}/s{}",
+                format_size(deletion.freed.min(deletion.total)),
+                format_size(deletion.total),
+                format_size(deletion.throughput() as u64),
+                eta
+            );
+            let gauge = Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    " {} {} directories, {} queued, {} at a time ",
+                    if app.delete_queue.is_paused() {
+                        "Paused deleting"
+                    } else {
+                        "Deleting"
+                    },
+                    format_number(app.delete_queue.running() as u64),
+                    format_number(app.delete_queue.queued() as u64),
+                    app.delete_queue.jobs_limit()
+                )))
+                .gauge_style(Style::default().fg(Color::Blue).bg(Color::Black))
+                .ratio(deletion.ratio())
+                .label(label);
+            frame.render_widget(gauge, chunks[2]);
+        }
 
         if app.is_in_search_mode {
             if let Some(filter_input) = app.filter_input.as_ref() {
@@ -186,6 +560,23 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
                 \n\
                 No Items found\n\
                 {} Files scanned\n\
+                {} Mount points skipped\n\
+                {} Paths inaccessible\n\
+                ",
+                    format_number(app.search_counter),
+                    format_number(app.skipped_mounts),
+                    format_number(app.scan_errors.len() as u64),
+                ),
+            )
+        } else if app.scan_control.is_cancelled() {
+            (
+                " Cancelled ".to_string(),
+                format!(
+                    "\
+                \n\
+                \n\
+                No Items found before the scan was cancelled\n\
+                {} Files scanned\n\
                 ",
                     format_number(app.search_counter),
                 ),
@@ -213,7 +604,243 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
                 )
                 .style(Style::default().fg(Color::Cyan).bg(Color::Black))
                 .alignment(Alignment::Center),
-            chunks[1],
+            list_area,
+        );
+    }
+
+    if let Some(area) = panel_area {
+        if app.show_report {
+            render_report(app, frame, area);
+        } else if app.show_failures {
+            render_failures(app, frame, area);
+        } else {
+            render_errors(app, frame, area);
+        }
+    }
+
+    if let Some(confirmation) = &app.confirmation {
+        render_confirmation(app, confirmation, frame);
+    }
+}
+
+/// Items listed per section of the confirmation.
+const CONFIRMATION_ITEMS: usize = 5;
+
+/// Renders the summary of what is about to be deleted, over the rest.
+fn render_confirmation<B: Backend>(
+    app: &App,
+    confirmation: &Confirmation,
+    frame: &mut Frame<'_, B>,
+) {
+    let item_line = |item: &DirEntryItem, detail: String| {
+        Line::from(format!(
+            "  {:>15}  [{}] {}",
+            detail,
+            item.root.display(),
+            display_name(item, app.show_project_names)
+        ))
+    };
+    let action = if app.delete_options.dry_run {
+        "Dry run: delete"
+    } else if app.delete_options.trash {
+        "Move to trash"
+    } else if app.delete_options.quarantine {
+        "Quarantine"
+    } else {
+        "Delete"
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!(
+                "{} {} directories, freeing {}?",
+                action,
+                format_number(confirmation.directories() as u64),
+                format_size(confirmation.size)
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from("Largest"),
+    ];
+    let mut largest: Vec<&DirEntryItem> = confirmation.items.iter().collect();
+    largest.sort_by_key(|item| Reverse(item.size));
+    lines.extend(
+        largest
+            .into_iter()
+            .take(CONFIRMATION_ITEMS)
+            .map(|item| item_line(item, format_size(item.size))),
+    );
+    let mut recent: Vec<(&DirEntryItem, Duration)> = confirmation
+        .items
+        .iter()
+        .filter_map(|item| Some((item, item.age()?)))
+        .collect();
+    if !recent.is_empty() {
+        recent.sort_by_key(|(_, age)| *age);
+        lines.push(Line::from(""));
+        lines.push(Line::from("Most recently active"));
+        lines.extend(
+            recent
+                .into_iter()
+                .take(CONFIRMATION_ITEMS)
+                .map(|(item, age)| item_line(item, format_age(age))),
         );
     }
+    lines.push(Line::from(""));
+    if confirmation.needs_typing {
+        lines.push(Line::from(format!(
+            "Type `{}` and press `Enter` to confirm, `Esc` to cancel",
+            CONFIRM_WORD
+        )));
+        lines.push(Line::from(Span::styled(
+            format!("> {}", confirmation.typed),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else {
+        lines.push(Line::from(
+            "Press `y` or `Enter` to confirm, `n` or `Esc` to cancel",
+        ));
+    }
+
+    let area = frame.size();
+    let width = area.width.saturating_sub(4).min(100);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Confirm ")
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+        popup,
+    );
+}
+
+/// Renders what the last dry run would have removed.
+fn render_report<B: Backend>(app: &App, frame: &mut Frame<'_, B>, area: Rect) {
+    let lines: Vec<Line> = if app.dry_run_report.is_empty() {
+        vec![Line::from("No dry run so far")]
+    } else {
+        app.dry_run_report
+            .iter()
+            .flat_map(|(paths, size)| {
+                // The size goes on the item's first directory, it covers all of them.
+                paths.iter().enumerate().map(move |(i, path)| {
+                    let size = if i == 0 {
+                        format_size(*size)
+                    } else {
+                        String::new()
+                    };
+                    Line::from(format!("{:>10}  {}", size, path.display()))
+                })
+            })
+            .collect()
+    };
+    let title = format!(
+        " Dry run: {} directories, {} would be freed - `v` to close, `up` and `down` to scroll ",
+        format_number(app.dry_run_directories() as u64),
+        format_size(app.dry_run_report.iter().map(|(_, size)| size).sum())
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Cyan).bg(Color::Black))
+            .scroll((app.report_scroll.try_into().unwrap_or(u16::MAX), 0)),
+        area,
+    );
+}
+
+/// Renders the deletions that failed, where and why, with what to do about it.
+fn render_failures<B: Backend>(app: &App, frame: &mut Frame<'_, B>, area: Rect) {
+    let lines: Vec<Line> = if app.delete_failures.is_empty() {
+        vec![Line::from("No failed deletions")]
+    } else {
+        app.delete_failures
+            .iter()
+            .flat_map(|(path, failure)| {
+                let mut lines = vec![
+                    Line::from(vec![
+                        Span::styled(path.display().to_string(), Style::default().fg(Color::Red)),
+                        Span::raw(format!(": {}", failure.kind.label())),
+                    ]),
+                    Line::from(format!(
+                        "  at {}: {}",
+                        failure.path.display(),
+                        failure.message
+                    )),
+                ];
+                if let Some(suggestion) = &failure.suggestion {
+                    lines.push(Line::from(Span::styled(
+                        format!("  fix: {}", suggestion),
+                        Style::default().fg(Color::Yellow),
+                    )));
+                }
+                lines
+            })
+            .collect()
+    };
+    let title = format!(
+        " Failed deletions ({}) - `t` to retry, `f` to close, `up` and `down` to scroll ",
+        format_number(app.delete_failures.len() as u64)
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Cyan).bg(Color::Black))
+            .scroll((app.failure_scroll.try_into().unwrap_or(u16::MAX), 0)),
+        area,
+    );
+}
+
+/// Renders the paths the scan couldn't read.
+fn render_errors<B: Backend>(app: &App, frame: &mut Frame<'_, B>, area: Rect) {
+    let lines: Vec<Line> = if app.scan_errors.is_empty() {
+        vec![Line::from("No errors so far")]
+    } else {
+        app.scan_errors
+            .iter()
+            .map(|(path, message)| {
+                Line::from(vec![
+                    Span::styled(path.display().to_string(), Style::default().fg(Color::Red)),
+                    Span::raw(format!(": {}", message)),
+                ])
+            })
+            .collect()
+    };
+    let title = format!(
+        " Scan errors ({}) - `e` to close, `up` and `down` to scroll ",
+        format_number(app.scan_errors.len() as u64)
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Cyan).bg(Color::Black))
+            .scroll((app.error_scroll.try_into().unwrap_or(u16::MAX), 0)),
+        area,
+    );
 }
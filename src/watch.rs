@@ -0,0 +1,197 @@
+use crate::dir_entry_item::DirEntryItem;
+use crate::effects::Walk;
+use crate::event::DirSearch;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the watches are polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a candidate has to stay unchanged before it is sized again.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// A watched directory.
+#[derive(Debug)]
+enum Watched {
+    /// A directory the walk went through, watched for candidates coming and going.
+    Dir { root: PathBuf, path: PathBuf },
+    /// A candidate, watched for its content changing.
+    Candidate {
+        path: PathBuf,
+        paths: Vec<PathBuf>,
+        /// Where its activity is worked out, see [`DirEntryItem::activity_root`].
+        activity_root: Option<PathBuf>,
+    },
+}
+
+impl Watched {
+    fn path(&self) -> &Path {
+        match self {
+            Watched::Dir { path, .. } | Watched::Candidate { path, .. } => path,
+        }
+    }
+}
+
+/// Keeps the list current after the scan, using inotify.
+///
+/// inotify isn't recursive: every directory the walk kept is watched for sub-directories
+/// being created, moved or removed, and every candidate for its entries changing. What
+/// happens deeper inside a candidate goes unnoticed until its top level changes too.
+pub struct Watcher {
+    inotify: Inotify,
+    watched: HashMap<WatchDescriptor, Watched>,
+    /// Candidates that changed, with when they last did.
+    changed: HashMap<PathBuf, Instant>,
+}
+
+impl Watcher {
+    pub fn new() -> io::Result<Watcher> {
+        Ok(Watcher {
+            inotify: Inotify::init()?,
+            watched: HashMap::new(),
+            changed: HashMap::new(),
+        })
+    }
+
+    /// Watches `path`, under `root`, as a candidate when `item` is given.
+    ///
+    /// Directories that can't be watched, typically once `max_user_watches` is reached,
+    /// are left out.
+    pub fn add(&mut self, root: &Path, path: &Path, item: Option<&DirEntryItem>) {
+        let (mask, watched) = match item {
+            Some(item) => (
+                WatchMask::CREATE
+                    | WatchMask::DELETE
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::MOVED_TO
+                    | WatchMask::MODIFY,
+                Watched::Candidate {
+                    path: path.to_path_buf(),
+                    paths: item.paths.clone(),
+                    activity_root: item.activity_root().map(Path::to_path_buf),
+                },
+            ),
+            None => (
+                WatchMask::CREATE | WatchMask::DELETE | WatchMask::MOVED_FROM | WatchMask::MOVED_TO,
+                Watched::Dir {
+                    root: root.to_path_buf(),
+                    path: path.to_path_buf(),
+                },
+            ),
+        };
+        if let Ok(wd) = self
+            .inotify
+            .watches()
+            .add(path, mask | WatchMask::ONLYDIR | WatchMask::DONT_FOLLOW)
+        {
+            self.watched.insert(wd, watched);
+        }
+    }
+
+    /// Reports changes under the roots until the scan is cancelled, or the inotify
+    /// instance fails. Changes made while paused are reported on resuming.
+    pub(crate) fn run(mut self, walk: &mut Walk) {
+        let mut buffer = [0; 4096];
+        while walk.control.proceed() {
+            let events: Vec<(WatchDescriptor, EventMask, Option<OsString>)> =
+                match self.inotify.read_events(&mut buffer) {
+                    Ok(events) => events
+                        .map(|event| (event.wd, event.mask, event.name.map(OsString::from)))
+                        .collect(),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => Vec::new(),
+                    Err(_) => return,
+                };
+            for (wd, mask, name) in events {
+                self.handle(walk, wd, mask, name);
+            }
+            self.size_settled(walk);
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn handle(
+        &mut self,
+        walk: &mut Walk,
+        wd: WatchDescriptor,
+        mask: EventMask,
+        name: Option<OsString>,
+    ) {
+        if mask.contains(EventMask::IGNORED) {
+            // The directory is gone, this may come before its parent reports it.
+            self.forget(walk, &wd);
+            return;
+        }
+        let Some(watched) = self.watched.get(&wd) else {
+            return;
+        };
+        match watched {
+            Watched::Dir { root, path } => {
+                let (Some(name), true) = (name, mask.contains(EventMask::ISDIR)) else {
+                    return;
+                };
+                let (root, path) = (root.clone(), path.join(name));
+                if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+                    // A new directory may already hold candidates, a moved in project does.
+                    walk.tree(&root, &path, &mut |path, item| self.add(&root, path, item));
+                } else if mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+                    self.remove_tree(walk, &path);
+                }
+            }
+            Watched::Candidate { path, .. } => {
+                self.changed.insert(path.clone(), Instant::now());
+            }
+        }
+    }
+
+    /// Forgets the watches at and below `path`, and reports the candidates among them.
+    fn remove_tree(&mut self, walk: &Walk, path: &Path) {
+        let gone: Vec<WatchDescriptor> = self
+            .watched
+            .iter()
+            .filter(|(_, watched)| watched.path().starts_with(path))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        for wd in gone {
+            // Removed directories lose their watch on their own, moved ones keep it.
+            let _ = self.inotify.watches().remove(wd.clone());
+            self.forget(walk, &wd);
+        }
+    }
+
+    /// Drops the watch `wd`, and reports its candidate as removed.
+    fn forget(&mut self, walk: &Walk, wd: &WatchDescriptor) {
+        if let Some(Watched::Candidate { path, .. }) = self.watched.remove(wd) {
+            self.changed.remove(&path);
+            walk.send(DirSearch::Removed(path));
+        }
+    }
+
+    /// Sizes again the candidates that stopped changing.
+    fn size_settled(&mut self, walk: &Walk) {
+        let settled: Vec<PathBuf> = self
+            .changed
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            self.changed.remove(&path);
+            let candidate = self.watched.values().find_map(|watched| match watched {
+                Watched::Candidate {
+                    path: candidate,
+                    paths,
+                    activity_root,
+                } if *candidate == path => Some((paths.clone(), activity_root.clone())),
+                _ => None,
+            });
+            if let Some((paths, activity_root)) = candidate {
+                walk.spawn_sizing(path, paths, activity_root);
+            }
+        }
+    }
+}
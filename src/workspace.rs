@@ -0,0 +1,128 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a monorepo declares its packages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceKind {
+    /// `workspaces` in `package.json`, used by npm, Yarn and Bun.
+    PackageJson,
+    /// `pnpm-workspace.yaml`.
+    Pnpm,
+    /// `lerna.json`.
+    Lerna,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Workspaces {
+    Packages(Vec<String>),
+    /// Yarn's `{ "packages": [...], "nohoist": [...] }`.
+    Object {
+        #[serde(default)]
+        packages: Vec<String>,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PackageJson {
+    workspaces: Option<Workspaces>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PnpmWorkspace {
+    packages: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LernaJson {
+    packages: Option<Vec<String>>,
+}
+
+/// Packages of a Lerna repository that doesn't list them.
+const LERNA_DEFAULT_PACKAGES: &str = "packages/*";
+
+/// A monorepo, whose packages get their dependencies installed together.
+///
+/// Deleting the `node_modules` of only some of them leaves the install broken, so the
+/// list offers them as one unit.
+#[derive(Debug)]
+pub struct Workspace {
+    /// Directory holding the declaration.
+    pub root: PathBuf,
+    pub kind: WorkspaceKind,
+    packages: GlobSet,
+    /// Patterns starting with `!`.
+    excluded: GlobSet,
+}
+
+impl Workspace {
+    /// Reads the workspace declared in `dir`, if any.
+    ///
+    /// `pnpm-workspace.yaml` wins over `workspaces` in `package.json`, which wins over
+    /// `lerna.json`. Declarations that can't be parsed are ignored.
+    pub fn declared_in(dir: &Path) -> Option<Workspace> {
+        let package: Option<PackageJson> = fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok());
+        let (kind, patterns) = if let Some(pnpm) = Self::read_pnpm(dir) {
+            (WorkspaceKind::Pnpm, pnpm.packages)
+        } else if let Some(workspaces) = package.and_then(|package| package.workspaces) {
+            let patterns = match workspaces {
+                Workspaces::Packages(packages) | Workspaces::Object { packages } => packages,
+            };
+            (WorkspaceKind::PackageJson, patterns)
+        } else {
+            let text = fs::read_to_string(dir.join("lerna.json")).ok()?;
+            let lerna: LernaJson = serde_json::from_str(&text).ok()?;
+            let patterns = lerna
+                .packages
+                .unwrap_or_else(|| vec![LERNA_DEFAULT_PACKAGES.to_string()]);
+            (WorkspaceKind::Lerna, patterns)
+        };
+        let mut packages = GlobSetBuilder::new();
+        let mut excluded = GlobSetBuilder::new();
+        for pattern in &patterns {
+            let (set, pattern) = match pattern.strip_prefix('!') {
+                Some(pattern) => (&mut excluded, pattern),
+                None => (&mut packages, pattern.as_str()),
+            };
+            let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+            if let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() {
+                set.add(glob);
+            }
+        }
+        Some(Workspace {
+            root: dir.to_path_buf(),
+            kind,
+            packages: packages.build().ok()?,
+            excluded: excluded.build().ok()?,
+        })
+    }
+
+    fn read_pnpm(dir: &Path) -> Option<PnpmWorkspace> {
+        let text = fs::read_to_string(dir.join("pnpm-workspace.yaml")).ok()?;
+        serde_yaml::from_str(&text).ok()
+    }
+
+    /// Whether the package at `package_root` is the workspace root or one of its packages.
+    pub fn contains(&self, package_root: &Path) -> bool {
+        match package_root.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => true,
+            Ok(relative) => self.packages.is_match(relative) && !self.excluded.is_match(relative),
+            Err(_) => false,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            WorkspaceKind::PackageJson => "workspace",
+            WorkspaceKind::Pnpm => "pnpm workspace",
+            WorkspaceKind::Lerna => "Lerna repository",
+        }
+    }
+}